@@ -0,0 +1,281 @@
+use super::lists::Navigate;
+use crate::error::{DooError, Result};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tui::widgets::ListState;
+
+/// path of the `n`th most recent backup of `path`, starting at 1
+pub fn backup_path(path: &str, n: usize) -> String {
+    format!("{}.{}.bak", path, n)
+}
+
+/// write a file without ever leaving a half written target behind.
+///
+/// the contents go to a temp file in the same directory, which is synced and then renamed
+/// over `path`. a symlink is followed, so the file it points at is replaced rather than the
+/// link, and the file keeps its permissions. when `backups` is non zero the previous contents
+/// of `path` are kept as `path.1.bak`, shifting older copies up to `path.<backups>.bak`
+pub fn write_atomic<F>(path: &str, backups: usize, write: F) -> Result<()>
+where
+    F: FnOnce(&mut BufWriter<&File>) -> Result<()>,
+{
+    let resolved = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+    let target = resolved.as_path();
+    let dir = match target.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| DooError::Command(format!("not a file path: {}", path)))?
+        .to_string_lossy();
+    let tmp_path = dir.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+    let tmp = tmp_path.to_string_lossy().to_string();
+
+    let result = (|| {
+        let file = File::create(&tmp_path).map_err(|e| DooError::io(&tmp, e))?;
+        let mut writer = BufWriter::new(&file);
+        write(&mut writer)?;
+        writer.flush().map_err(|e| DooError::io(&tmp, e))?;
+        drop(writer);
+        file.sync_all().map_err(|e| DooError::io(&tmp, e))?;
+
+        if let Ok(metadata) = fs::metadata(target) {
+            fs::set_permissions(&tmp_path, metadata.permissions())
+                .map_err(|e| DooError::io(&tmp, e))?;
+        }
+
+        if backups > 0 && target.exists() {
+            rotate(path, backups)?;
+        }
+
+        fs::rename(&tmp_path, target).map_err(|e| DooError::io(path, e))
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+        return result;
+    }
+
+    // make the rename itself durable. not every platform can open a directory, so this is
+    // best effort
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+/// shift every backup of `path` up by one and copy the current file into the first slot
+fn rotate(path: &str, backups: usize) -> Result<()> {
+    let oldest = backup_path(path, backups);
+    if Path::new(&oldest).exists() {
+        fs::remove_file(&oldest).map_err(|e| DooError::io(&oldest, e))?;
+    }
+
+    for n in (1..backups).rev() {
+        let from = backup_path(path, n);
+        if Path::new(&from).exists() {
+            fs::rename(&from, backup_path(path, n + 1)).map_err(|e| DooError::io(&from, e))?;
+        }
+    }
+
+    let newest = backup_path(path, 1);
+    fs::copy(path, &newest).map_err(|e| DooError::io(&newest, e))?;
+    Ok(())
+}
+
+pub struct Backup {
+    pub path: String,
+    pub modified: Option<SystemTime>,
+}
+
+impl Backup {
+    /// how long ago the backup was written, e.g. "5m ago"
+    pub fn age(&self) -> String {
+        let secs = match self.modified.and_then(|m| m.elapsed().ok()) {
+            Some(elapsed) => elapsed.as_secs(),
+            None => return String::from("unknown age"),
+        };
+
+        match secs {
+            0..=59 => format!("{}s ago", secs),
+            60..=3599 => format!("{}m ago", secs / 60),
+            3600..=86399 => format!("{}h ago", secs / 3600),
+            _ => format!("{}d ago", secs / 86400),
+        }
+    }
+}
+
+/// the backups of a list that can be restored from the ':backups' screen
+pub struct Backups {
+    pub items: Vec<Backup>,
+    pub state: ListState,
+}
+
+impl Backups {
+    pub fn new() -> Backups {
+        Backups {
+            items: Vec::new(),
+            state: ListState::default(),
+        }
+    }
+
+    /// collect the existing backups of `path`, newest first
+    pub fn find(path: &str, backups: usize) -> Backups {
+        let mut found = Backups::new();
+        // look one past the configured count, in case it was lowered since
+        for n in 1..=backups + 1 {
+            let backup = backup_path(path, n);
+            if let Ok(metadata) = fs::metadata(&backup) {
+                found.items.push(Backup {
+                    path: backup,
+                    modified: metadata.modified().ok(),
+                });
+            }
+        }
+        found.next();
+        found
+    }
+
+    pub fn select(&self) -> Option<String> {
+        self.state
+            .selected()
+            .and_then(|i| self.items.get(i))
+            .map(|b| b.path.to_string())
+    }
+}
+
+impl Navigate for Backups {
+    fn previous(&mut self) {
+        if !self.items.is_empty() {
+            let i = match self.state.selected() {
+                Some(i) => i.saturating_sub(1),
+                None => 0,
+            };
+            self.state.select(Some(i));
+        }
+    }
+
+    fn next(&mut self) {
+        if !self.items.is_empty() {
+            let i = match self.state.selected() {
+                Some(i) => (i + 1).min(self.items.len() - 1),
+                None => 0,
+            };
+            self.state.select(Some(i));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("doo-backup-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(path: &str, backups: usize, text: &str) -> Result<()> {
+        write_atomic(path, backups, |writer| {
+            writer
+                .write_all(text.as_bytes())
+                .map_err(|e| DooError::io(path, e))
+        })
+    }
+
+    fn read(path: &str) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn backups_rotate_and_the_oldest_is_dropped() {
+        let dir = scratch("rotate");
+        let path = dir.join("list.json").to_string_lossy().to_string();
+        for text in ["first", "second", "third", "fourth"] {
+            write(&path, 2, text).unwrap();
+        }
+
+        assert_eq!(read(&path), "fourth");
+        assert_eq!(read(&backup_path(&path, 1)), "third");
+        assert_eq!(read(&backup_path(&path, 2)), "second");
+        assert!(!Path::new(&backup_path(&path, 3)).exists());
+
+        let found = Backups::find(&path, 2);
+        let paths: Vec<&str> = found.items.iter().map(|b| b.path.as_str()).collect();
+        assert_eq!(paths, vec![backup_path(&path, 1), backup_path(&path, 2)]);
+        assert_eq!(found.select(), Some(backup_path(&path, 1)));
+
+        // no backups asked for, none made
+        let plain = dir.join("plain.json").to_string_lossy().to_string();
+        write(&plain, 0, "one").unwrap();
+        write(&plain, 0, "two").unwrap();
+        assert!(Backups::find(&plain, 3).items.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_failed_write_leaves_the_file_and_no_temp_behind() {
+        let dir = scratch("failed");
+        let path = dir.join("list.json").to_string_lossy().to_string();
+        write(&path, 1, "kept").unwrap();
+
+        let result = write_atomic(&path, 1, |_| Err(DooError::Command(String::from("boom"))));
+        assert!(result.is_err());
+        assert_eq!(read(&path), "kept");
+        assert!(!Path::new(&backup_path(&path, 1)).exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions_are_kept() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = scratch("mode");
+        let path = dir.join("list.json").to_string_lossy().to_string();
+        write(&path, 0, "first").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        write(&path, 0, "second").unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_written_through() {
+        let dir = scratch("link");
+        fs::create_dir_all(dir.join("real")).unwrap();
+        let real = dir.join("real").join("list.json");
+        let link = dir.join("list.json");
+        fs::write(&real, "first").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        let link = link.to_string_lossy().to_string();
+
+        write(&link, 1, "second").unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(read(&real.to_string_lossy()), "second");
+        assert_eq!(read(&backup_path(&link, 1)), "first");
+        // the temp file was made next to the real file, and is gone again
+        assert_eq!(fs::read_dir(dir.join("real")).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,167 @@
+use super::{queue::CappedQueue, state};
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+
+/// command lines run before, newest first, recalled with up and down in command mode and kept
+/// between sessions
+#[derive(Serialize, Deserialize)]
+pub struct CommandHistory {
+    pub queue: CappedQueue<String>,
+    /// the entry being shown while browsing, none while typing a new line
+    #[serde(skip)]
+    cursor: Option<usize>,
+    /// what was typed before browsing started, given back when browsing past the newest entry
+    #[serde(skip)]
+    draft: String,
+}
+
+impl CommandHistory {
+    pub fn new(capacity: usize) -> CommandHistory {
+        CommandHistory {
+            queue: CappedQueue::new(capacity),
+            cursor: None,
+            draft: String::new(),
+        }
+    }
+
+    /// load the history at `path`, starting an empty one if it does not exist yet
+    pub fn load(path: &str, capacity: usize) -> Result<CommandHistory> {
+        let mut history: CommandHistory = match state::load(path)? {
+            Some(history) => history,
+            None => return Ok(CommandHistory::new(capacity)),
+        };
+
+        // the configured capacity wins over whatever was stored
+        history.queue.capacity = capacity;
+        history.queue.items.truncate(capacity);
+        Ok(history)
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        state::save(path, self)
+    }
+
+    /// remember a command line that was run. running one again moves it to the front
+    pub fn add(&mut self, line: &str) {
+        self.reset();
+        let line = line.trim();
+        if line.is_empty() || self.queue.capacity == 0 {
+            return;
+        }
+        self.queue.items.retain(|l| l != line);
+        if self.queue.is_full() {
+            self.queue.pop_back();
+        }
+        let _ = self.queue.push_front(line.to_string());
+    }
+
+    /// the entry before the one shown, or the newest when `input` is still being typed
+    pub fn older(&mut self, input: &str) -> Option<String> {
+        let i = match self.cursor {
+            None => {
+                self.draft = input.to_string();
+                0
+            }
+            Some(i) => i + 1,
+        };
+        let line = self.queue.items.get(i)?.clone();
+        self.cursor = Some(i);
+        Some(line)
+    }
+
+    /// the entry after the one shown, and finally the line typed before browsing
+    pub fn newer(&mut self) -> Option<String> {
+        match self.cursor? {
+            0 => {
+                self.cursor = None;
+                Some(std::mem::take(&mut self.draft))
+            }
+            i => {
+                self.cursor = Some(i - 1);
+                self.queue.items.get(i - 1).cloned()
+            }
+        }
+    }
+
+    /// stop browsing, as when the command line is run or dropped
+    pub fn reset(&mut self) {
+        self.cursor = None;
+        self.draft.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn recalls_newest_first_and_gives_back_the_draft() {
+        let mut history = CommandHistory::new(3);
+        for line in ["w", "sort due", "e list.json", "w"] {
+            history.add(line);
+        }
+        assert_eq!(history.queue.items, vec!["w", "e list.json", "sort due"]);
+
+        assert_eq!(history.older("fil").as_deref(), Some("w"));
+        assert_eq!(history.older("").as_deref(), Some("e list.json"));
+        assert_eq!(history.older("").as_deref(), Some("sort due"));
+        assert_eq!(history.older(""), None);
+        assert_eq!(history.newer().as_deref(), Some("e list.json"));
+        assert_eq!(history.newer().as_deref(), Some("w"));
+        assert_eq!(history.newer().as_deref(), Some("fil"));
+        assert_eq!(history.newer(), None);
+    }
+
+    #[test]
+    fn blank_lines_and_a_zero_capacity_remember_nothing() {
+        let mut history = CommandHistory::new(2);
+        history.add("   ");
+        history.add(" w ");
+        assert_eq!(history.queue.items, vec!["w"]);
+
+        let mut none = CommandHistory::new(0);
+        none.add("w");
+        assert!(none.queue.items.is_empty());
+        assert_eq!(none.older("typed"), None);
+        assert_eq!(none.newer(), None);
+    }
+
+    #[test]
+    fn running_a_line_stops_browsing() {
+        let mut history = CommandHistory::new(5);
+        history.add("w");
+        history.add("q");
+        assert_eq!(history.older("draft").as_deref(), Some("q"));
+        history.add("sort due");
+        // browsing starts over from the newest, and the old draft is gone
+        assert_eq!(history.older("").as_deref(), Some("sort due"));
+        assert_eq!(history.newer().as_deref(), Some(""));
+    }
+
+    #[test]
+    fn the_configured_capacity_wins_when_loading() {
+        let dir = std::env::temp_dir().join(format!("doo-history-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("history.json").to_string_lossy().to_string();
+        assert!(CommandHistory::load(&path, 3)
+            .unwrap()
+            .queue
+            .items
+            .is_empty());
+
+        let mut history = CommandHistory::new(5);
+        for line in ["a", "b", "c", "d"] {
+            history.add(line);
+        }
+        history.save(&path).unwrap();
+
+        let loaded = CommandHistory::load(&path, 2).unwrap();
+        assert_eq!(loaded.queue.items, vec!["d", "c"]);
+        assert_eq!(loaded.queue.capacity, 2);
+
+        fs::write(&path, "not json").unwrap();
+        assert!(CommandHistory::load(&path, 2).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
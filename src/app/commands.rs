@@ -1,46 +1,52 @@
-use super::{lists::Navigate, DooList, Mode, RecentFiles, Screen};
+use super::formats::Format;
+use super::{backup::Backups, lists::Navigate, storage, DooList, Mode, RecentFiles, Screen};
+use crate::config::DooConfig;
+use crate::error::{DooError, Result};
 
 // TODO: refactor
+/// save to `input`, or to the current path if none is given. returns the path written to.
+/// a list without a path yet takes on `input`, while a copy written anywhere else leaves the
+/// open file's unsaved changes unsaved
 pub fn saveas(
     input: Option<String>,
     doolist: &mut DooList,
     recent_files: &mut RecentFiles,
-    current_path: &Option<String>,
-) {
-    match input {
-        Some(path) => {
-            eprintln!("saving to {}", path);
-            doolist.save(&path).unwrap();
-            recent_files.add_recent(path.to_string());
-        }
-        None => {
-            if let Some(path) = &current_path {
-                eprintln!("saving to {}", path);
-                doolist.save(&path).unwrap();
-                recent_files.add_recent(path.to_string());
-            }
-        }
+    current_path: &mut Option<String>,
+    config: &DooConfig,
+) -> Result<String> {
+    let path = match input {
+        Some(path) => path,
+        None => current_path
+            .clone()
+            .ok_or_else(|| DooError::Command(String::from("no file name, use ':w <path>'")))?,
+    };
+
+    storage::open(&path, config).save(doolist, &path)?;
+    if current_path.is_none() {
+        *current_path = Some(path.clone());
     }
+    if current_path.as_deref() == Some(path.as_str()) {
+        doolist.modified = false;
+    }
+    recent_files.add_recent(path.to_string());
+    Ok(path)
 }
 
-// BUG: should propogate an error on load failure
 pub fn load(
     input: Option<String>,
     doolist: &mut DooList,
     recent_files: &mut RecentFiles,
     current_path: &mut Option<String>,
-) {
-    if let Some(path) = input {
-        match DooList::load(&path) {
-            Ok(list) => {
-                *doolist = list;
-                *current_path = Some(path.to_string());
-                recent_files.add_recent(path.to_string());
-                doolist.next();
-            }
-            Err(e) => eprintln!("that was an error {}", e),
-        }
-    }
+    config: &DooConfig,
+) -> Result<String> {
+    let path =
+        input.ok_or_else(|| DooError::Command(String::from("no file name, use ':e <path>'")))?;
+
+    *doolist = storage::open(&path, config).load(&path)?;
+    *current_path = Some(path.to_string());
+    recent_files.add_recent(path.to_string());
+    doolist.next();
+    Ok(path)
 }
 
 pub fn new(doolist: &mut DooList, current_path: &mut Option<String>) {
@@ -48,10 +54,10 @@ pub fn new(doolist: &mut DooList, current_path: &mut Option<String>) {
     *doolist = DooList::new();
 }
 
-pub fn rename(input: Option<&&str>, doolist_name: &mut Option<String>) {
-    if let Some(name) = input {
-        *doolist_name = Some(name.to_string());
-    }
+pub fn rename(input: Option<&&str>, doolist: &mut DooList) -> Result<()> {
+    let name = input.ok_or_else(|| DooError::Command(String::from("usage: ':rename <name>'")))?;
+    doolist.change_name(name.to_string());
+    Ok(())
 }
 
 pub fn help(screen: &mut Screen, mode: &mut Mode) {
@@ -64,6 +70,69 @@ pub fn recent(screen: &mut Screen, mode: &mut Mode) {
     *mode = Mode::Select;
 }
 
+pub fn backups(
+    current_path: &Option<String>,
+    count: usize,
+    backups: &mut Backups,
+    screen: &mut Screen,
+    mode: &mut Mode,
+) -> Result<()> {
+    let path = current_path
+        .as_ref()
+        .ok_or_else(|| DooError::Command(String::from("list has no path, so no backups")))?;
+    *backups = Backups::find(path, count);
+    if backups.items.is_empty() {
+        return Err(DooError::Command(format!("no backups of {}", path)));
+    }
+
+    *screen = Screen::Backups;
+    *mode = Mode::Select;
+    Ok(())
+}
+
+/// replace the list with the contents of a backup. the list keeps its own path and is left
+/// modified, so the restore only sticks once it is written
+pub fn restore(
+    input: Option<String>,
+    current_path: &Option<String>,
+    doolist: &mut DooList,
+    config: &DooConfig,
+) -> Result<String> {
+    let path = input.ok_or_else(|| DooError::Command(String::from("no backup selected")))?;
+    // a backup is in the same format as the list it was taken of
+    let list_path = current_path.as_deref().unwrap_or(&path);
+    *doolist = storage::open(list_path, config).load(&path)?;
+    doolist.modified = true;
+    doolist.next();
+    Ok(path)
+}
+
 pub fn quit(quit_state: &mut bool) {
     *quit_state = true;
 }
+
+/// write the list to `path` in another format, leaving the list itself and its path alone
+pub fn export(
+    format: Option<&&str>,
+    path: Option<String>,
+    doolist: &DooList,
+    backups: usize,
+) -> Result<String> {
+    let usage = || DooError::Command(String::from("usage: ':export <todo.txt|md|doo> <path>'"));
+    let format = format.and_then(|f| Format::parse(f)).ok_or_else(usage)?;
+    let path = path.ok_or_else(usage)?;
+
+    storage::with_format(format, backups).save(doolist, &path)?;
+    Ok(path)
+}
+
+/// append the tasks of another file to the list, guessing its format from the extension.
+/// returns how many top level tasks were added
+pub fn import(input: Option<String>, doolist: &mut DooList) -> Result<usize> {
+    let path = input.ok_or_else(|| DooError::Command(String::from("usage: ':import <path>'")))?;
+
+    let imported = storage::with_format(Format::from_path(&path), 0).load(&path)?;
+    let count = imported.list.len();
+    doolist.append(imported.list);
+    Ok(count)
+}
@@ -0,0 +1,223 @@
+use super::super::utils;
+use std::fs;
+use std::path::Path;
+
+/// every command ':' understands, offered when completing the first word
+const COMMANDS: &[&str] = &[
+    "backups",
+    "due",
+    "e",
+    "export",
+    "filter",
+    "help",
+    "import",
+    "lists",
+    "load",
+    "m",
+    "move",
+    "new",
+    "path",
+    "pri",
+    "priority",
+    "q",
+    "recent",
+    "reg",
+    "registers",
+    "rename",
+    "save",
+    "saveas",
+    "sort",
+    "tag",
+    "tags",
+    "w",
+    "wq",
+];
+
+/// commands whose last argument is a file path
+const PATH_COMMANDS: &[&str] = &["e", "load", "w", "saveas", "import", "export", "lists"];
+
+const SORT_KEYS: &[&str] = &["done", "due", "label", "priority"];
+
+/// the choices for the word under the cursor, cycled through with tab
+pub struct Completion {
+    pub candidates: Vec<String>,
+    /// the candidate in the input, none until tab is pressed again
+    pub selected: Option<usize>,
+    /// where the completed word starts in the input
+    start: usize,
+}
+
+impl Completion {
+    /// complete the last word of a command line. returns none when nothing fits
+    pub fn new(input: &str) -> Option<Completion> {
+        let start = input.rfind(' ').map_or(0, |i| i + 1);
+        let word = &input[start..];
+        let command = input.split(' ').next().unwrap_or("").trim_end_matches('!');
+
+        let candidates: Vec<String> = match start {
+            0 => COMMANDS
+                .iter()
+                .filter(|c| c.starts_with(word))
+                .map(|c| c.to_string())
+                .collect(),
+            _ if PATH_COMMANDS.contains(&command) => paths(word),
+            _ if command == "sort" => SORT_KEYS
+                .iter()
+                .filter(|k| k.starts_with(word))
+                .map(|k| k.to_string())
+                .collect(),
+            _ => Vec::new(),
+        };
+        match candidates.is_empty() {
+            true => None,
+            false => Some(Completion {
+                candidates,
+                selected: None,
+                start,
+            }),
+        }
+    }
+
+    /// the longest start shared by every candidate, which is as far as tab can complete
+    /// before a choice has to be made
+    pub fn common_prefix(&self) -> String {
+        let mut prefix = self.candidates[0].clone();
+        for candidate in &self.candidates[1..] {
+            let shared = prefix
+                .char_indices()
+                .zip(candidate.chars())
+                .find(|((_, a), b)| a != b)
+                .map_or(prefix.len().min(candidate.len()), |((i, _), _)| i);
+            prefix.truncate(shared);
+        }
+        prefix
+    }
+
+    /// choose the next candidate, or the previous one when `back` is set
+    pub fn cycle(&mut self, back: bool) {
+        let len = self.candidates.len();
+        self.selected = Some(match (self.selected, back) {
+            (None, false) => 0,
+            (None, true) => len - 1,
+            (Some(i), false) => (i + 1) % len,
+            (Some(i), true) => (i + len - 1) % len,
+        });
+    }
+
+    /// `input` with its last word replaced by `word`
+    pub fn apply(&self, input: &str, word: &str) -> String {
+        format!("{}{}", &input[..self.start.min(input.len())], word)
+    }
+}
+
+/// files and directories starting with `word`, as typed, with directories ending in '/'.
+/// relative paths are looked up from the working directory
+fn paths(word: &str) -> Vec<String> {
+    let (dir, name) = match word.rfind('/') {
+        Some(i) => word.split_at(i + 1),
+        None => ("", word),
+    };
+    let lookup = match dir {
+        "" => utils::get_abs_path_from(String::from(".")),
+        dir => utils::get_abs_path_from(utils::expand_home(dir)),
+    };
+    let entries = match fs::read_dir(Path::new(&lookup)) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut paths: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            // hidden files only when asked for
+            let wanted = file_name.starts_with(name)
+                && (name.starts_with('.') || !file_name.starts_with('.'));
+            let slash = match entry.path().is_dir() {
+                true => "/",
+                false => "",
+            };
+            wanted.then(|| format!("{}{}{}", dir, file_name, slash))
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::storage;
+
+    #[test]
+    fn first_word_completes_commands() {
+        let completion = Completion::new("re").unwrap();
+        assert_eq!(
+            completion.candidates,
+            vec!["recent", "reg", "registers", "rename"]
+        );
+        assert_eq!(completion.common_prefix(), "re");
+        assert!(Completion::new("zz").is_none());
+        assert!(Completion::new("tag x").is_none());
+    }
+
+    #[test]
+    fn cycling_wraps_both_ways() {
+        let mut completion = Completion::new("sort d").unwrap();
+        assert_eq!(completion.candidates, vec!["done", "due"]);
+        completion.cycle(true);
+        assert_eq!(completion.selected, Some(1));
+        completion.cycle(false);
+        assert_eq!(completion.selected, Some(0));
+        assert_eq!(completion.apply("sort d", "due"), "sort due");
+    }
+
+    #[test]
+    fn completed_paths_open_what_was_listed() {
+        let dir = std::env::temp_dir().join(format!("doo-completion-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("lists")).unwrap();
+        std::fs::write(dir.join("list.json"), "").unwrap();
+        std::fs::write(dir.join(".hidden.json"), "").unwrap();
+
+        let word = format!("{}/li", dir.display());
+        let completion = Completion::new(&format!("e {}", word)).unwrap();
+        assert_eq!(
+            completion.candidates,
+            vec![
+                format!("{}/list.json", dir.display()),
+                format!("{}/lists/", dir.display())
+            ]
+        );
+        assert_eq!(
+            completion.common_prefix(),
+            format!("{}/list", dir.display())
+        );
+        assert!(Completion::new(&format!("e {}/.", dir.display()))
+            .unwrap()
+            .candidates
+            .contains(&format!("{}/.hidden.json", dir.display())));
+
+        for candidate in &completion.candidates {
+            assert!(Path::new(&storage::resolve(candidate)).exists());
+        }
+    }
+
+    #[test]
+    fn paths_under_home_keep_the_tilde_and_still_open() {
+        let home = std::env::var("HOME").unwrap();
+        assert_eq!(
+            storage::resolve("~/list.json"),
+            format!("{}/list.json", home)
+        );
+        assert_eq!(storage::resolve("db://work"), "db://work");
+
+        // whatever the home directory holds, each offer must open from there, not from '<cwd>/~'
+        if let Some(completion) = Completion::new("e ~/.") {
+            for candidate in &completion.candidates {
+                assert!(candidate.starts_with("~/"));
+                assert!(Path::new(&storage::resolve(candidate)).exists());
+            }
+        }
+    }
+}
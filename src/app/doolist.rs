@@ -1,32 +1,186 @@
-use core::fmt::Display;
+use chrono::{Local, NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
-use serde_json;
-use std::error::Error;
-use std::fs::{File, OpenOptions};
-use std::io::{BufReader, BufWriter};
-use std::path::Path;
+use std::collections::BTreeSet;
 use tui::widgets::ListState;
 
-use super::lists;
+use super::history::{Edit, History};
+use super::{lists, tags};
+use crate::error::{DooError, Result};
 
-#[derive(Serialize, Deserialize, Clone)]
+/// position of an item in the tree, as a chain of indices from the top level down
+pub type ItemPath = Vec<usize>;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct DooItem {
     pub label: String,
     pub complete: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subtasks: Vec<DooItem>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub folded: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<NaiveDateTime>,
+    /// todo.txt style priority, 'A' being the highest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<char>,
+    /// the '#tags' and '@contexts' found in the label, kept in sync by `change_label`
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<NaiveDate>,
+    /// the day the item was last marked complete, cleared when it is unmarked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed: Option<NaiveDate>,
 }
 
 impl DooItem {
     pub fn new(label: String, complete: bool) -> DooItem {
-        DooItem { label, complete }
+        DooItem {
+            tags: tags::parse(&label),
+            label,
+            complete,
+            subtasks: Vec::new(),
+            folded: false,
+            notes: String::new(),
+            due: None,
+            priority: None,
+            created: Some(Local::now().date_naive()),
+            completed: None,
+        }
     }
 
     pub fn change_label(&mut self, label: String) {
+        self.tags = tags::parse(&label);
         self.label = label
     }
 
+    /// re-read the tags of this item and its subtasks from their labels, for files written by
+    /// hand or before tags existed
+    fn refresh_tags(&mut self) {
+        self.tags = tags::parse(&self.label);
+        for subtask in self.subtasks.iter_mut() {
+            subtask.refresh_tags();
+        }
+    }
+
     pub fn mark(&mut self) {
-        self.complete = !self.complete
+        let complete = !self.complete;
+        self.set_complete(complete);
+    }
+
+    /// set completion for this item and every subtask below it
+    pub fn set_complete(&mut self, complete: bool) {
+        self.stamp_complete(complete);
+        for subtask in self.subtasks.iter_mut() {
+            subtask.set_complete(complete);
+        }
+    }
+
+    /// raise the priority by one level. an item without one starts at 'C', the lowest of
+    /// high ('A'), med ('B') and low ('C')
+    pub fn raise_priority(&mut self) {
+        self.priority = match self.priority {
+            None => Some('C'),
+            Some('A') => Some('A'),
+            Some(p) => Some((p as u8 - 1) as char),
+        };
+    }
+
+    /// lower the priority by one level, clearing it once it drops below 'C'. the todo.txt
+    /// letters past 'C' step down one at a time until 'Z'
+    pub fn lower_priority(&mut self) {
+        self.priority = match self.priority {
+            Some('C') | Some('Z') | None => None,
+            Some(p) => Some((p as u8 + 1) as char),
+        };
+    }
+
+    pub fn has_notes(&self) -> bool {
+        !self.notes.is_empty()
+    }
+
+    pub fn has_subtasks(&self) -> bool {
+        !self.subtasks.is_empty()
+    }
+
+    /// a parent is complete exactly when all of its subtasks are
+    fn sync_complete(&mut self) {
+        if self.has_subtasks() {
+            let complete = self.subtasks.iter().all(|s| s.complete);
+            self.stamp_complete(complete);
+        }
     }
+
+    /// set completion alone, dating it when the item becomes complete
+    fn stamp_complete(&mut self, complete: bool) {
+        self.completed = match (complete, self.complete) {
+            (true, true) => self.completed,
+            (true, false) => Some(Local::now().date_naive()),
+            (false, _) => None,
+        };
+        self.complete = complete;
+    }
+}
+
+/// parse a priority as given on the command line: a letter from 'A' to 'Z', one of
+/// high/med/low, or none. returns `Some(None)` for none
+pub fn parse_priority(input: &str) -> Option<Option<char>> {
+    match input.to_lowercase().as_str() {
+        "none" | "-" => Some(None),
+        "high" | "h" => Some(Some('A')),
+        "med" | "medium" | "m" => Some(Some('B')),
+        "low" | "l" => Some(Some('C')),
+        _ => {
+            let mut chars = input.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii_alphabetic() => Some(Some(c.to_ascii_uppercase())),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// what ':sort' orders items by
+#[derive(Clone, Copy)]
+pub enum SortKey {
+    /// highest priority first, items without one last
+    Priority,
+    /// soonest due first, items without a due date last
+    Due,
+    /// pending items before completed ones
+    Done,
+    Label,
+}
+
+impl SortKey {
+    pub fn parse(input: &str) -> Option<SortKey> {
+        match input {
+            "priority" | "pri" => Some(SortKey::Priority),
+            "due" => Some(SortKey::Due),
+            "done" | "complete" => Some(SortKey::Done),
+            "label" | "name" => Some(SortKey::Label),
+            _ => None,
+        }
+    }
+
+    fn compare(&self, a: &DooItem, b: &DooItem) -> std::cmp::Ordering {
+        match self {
+            SortKey::Priority => {
+                (a.priority.is_none(), a.priority).cmp(&(b.priority.is_none(), b.priority))
+            }
+            SortKey::Due => (a.due.is_none(), a.due).cmp(&(b.due.is_none(), b.due)),
+            SortKey::Done => a.complete.cmp(&b.complete),
+            SortKey::Label => a.label.to_lowercase().cmp(&b.label.to_lowercase()),
+        }
+    }
+}
+
+/// a row of the list as it appears on screen, after folding is applied
+pub struct VisibleItem {
+    pub path: ItemPath,
+    pub depth: usize,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -37,6 +191,20 @@ pub struct DooList {
     pub state: ListState,
     #[serde(skip)]
     pub path: Option<String>,
+    /// set by every edit, cleared once the list is written to disk
+    #[serde(skip)]
+    pub modified: bool,
+    #[serde(skip)]
+    pub history: History,
+    /// only items carrying every one of these tags, and their ancestors, are shown
+    #[serde(skip)]
+    pub filter: Vec<String>,
+}
+
+impl Default for DooList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DooList {
@@ -46,109 +214,888 @@ impl DooList {
             list: Vec::new(),
             state: ListState::default(),
             path: None,
+            modified: false,
+            history: History::default(),
+            filter: Vec::new(),
         }
     }
 
-    // TODO: impl error handling for this function
-    pub fn load(path: &String) -> Result<DooList, &'static str> {
-        let file = match File::open(path) {
-            Ok(file) => file,
-            Err(_) => return Err("failed to find/open file"), //TODO : propogate
+    /// re-read the tags of every item from its label, for lists written by hand or before tags
+    /// existed
+    pub fn refresh_tags(&mut self) {
+        for item in self.list.iter_mut() {
+            item.refresh_tags();
+        }
+    }
+
+    pub fn change_name(&mut self, new_name: String) {
+        let before = self.name.replace(new_name);
+        self.record(Edit::Rename {
+            before,
+            after: self.name.clone(),
+        });
+    }
+
+    pub fn add_from_label(&mut self, label: String) {
+        let label = self.with_filter_tags(&[], label);
+        self.add_from_item(DooItem::new(label, false));
+    }
+
+    pub fn add_from_item(&mut self, item: DooItem) {
+        self.list.push(item.clone());
+        self.record(Edit::Insert {
+            path: vec![self.list.len() - 1],
+            item,
+            ancestors: Vec::new(),
+        });
+        self.select_last()
+    }
+
+    fn select_last(&mut self) {
+        self.select_path(&[self.list.len() - 1]);
+    }
+
+    /// every row that is currently drawn, walking the tree depth first and skipping the
+    /// children of folded items and anything the filter hides
+    pub fn visible(&self) -> Vec<VisibleItem> {
+        self.walk(true, &self.filter)
+    }
+
+    /// the path of every item in the tree, depth first, including folded and filtered ones
+    pub fn paths(&self) -> Vec<ItemPath> {
+        self.walk(false, &[]).into_iter().map(|v| v.path).collect()
+    }
+
+    /// the path of every item the filter lets through, including folded ones
+    pub fn filtered_paths(&self) -> Vec<ItemPath> {
+        self.walk(false, &self.filter)
+            .into_iter()
+            .map(|v| v.path)
+            .collect()
+    }
+
+    /// walk the tree depth first. an item is kept when it, together with the tags it inherits
+    /// from its ancestors, has every tag in `filter`, or when any of its subtasks is kept
+    fn walk(&self, respect_folds: bool, filter: &[String]) -> Vec<VisibleItem> {
+        struct Walk<'a> {
+            folds: bool,
+            filter: &'a [String],
+        }
+
+        fn walk(
+            w: &Walk,
+            items: &[DooItem],
+            parent: &[usize],
+            inherited: &BTreeSet<String>,
+            out: &mut Vec<VisibleItem>,
+        ) -> bool {
+            let mut any = false;
+            for (i, item) in items.iter().enumerate() {
+                let mut path = parent.to_vec();
+                path.push(i);
+                let tags: BTreeSet<String> = inherited.union(&item.tags).cloned().collect();
+                let matched = w.filter.iter().all(|t| tags.contains(t));
+
+                let mut below = Vec::new();
+                let below_matched = walk(w, &item.subtasks, &path, &tags, &mut below);
+                if !(matched || below_matched) {
+                    continue;
+                }
+
+                any = true;
+                out.push(VisibleItem {
+                    depth: parent.len(),
+                    path,
+                });
+                if !(w.folds && item.folded) {
+                    out.append(&mut below);
+                }
+            }
+            any
+        }
+
+        let mut out = Vec::new();
+        let w = Walk {
+            folds: respect_folds,
+            filter,
         };
+        walk(&w, &self.list, &[], &BTreeSet::new(), &mut out);
+        out
+    }
 
-        let reader = BufReader::new(file);
+    /// narrow the view to items carrying every tag in `filter`, or show everything again when
+    /// it is empty. the selection stays put when it is still shown
+    pub fn set_filter(&mut self, filter: Vec<String>) {
+        let selected = self.selected_path();
+        self.filter = filter;
+        match selected {
+            Some(path) if self.visible().iter().any(|v| v.path == path) => self.select_path(&path),
+            _ => {
+                let visible = self.visible().len();
+                self.state.select(match visible {
+                    0 => None,
+                    _ => Some(0),
+                });
+            }
+        }
+    }
 
-        match serde_json::from_reader(reader) {
-            Ok(list) => return Ok(list),
-            Err(_) => return Err("failed to get list from file"), //TODO : propogate
+    /// append the filter tags that an item added below `parent` would not already have or
+    /// inherit, so that new items do not vanish from a filtered view
+    fn with_filter_tags(&self, parent: &[usize], label: String) -> String {
+        let mut inherited = tags::parse(&label);
+        for depth in 1..=parent.len() {
+            if let Some(item) = self.get(&parent[..depth]) {
+                inherited.extend(item.tags.iter().cloned());
+            }
+        }
+
+        let missing: Vec<&str> = self
+            .filter
+            .iter()
+            .filter(|t| !inherited.contains(*t))
+            .map(|t| t.as_str())
+            .collect();
+        match missing.is_empty() {
+            true => label,
+            false => format!("{} {}", label, missing.join(" ")),
         }
     }
 
-    pub fn save(&self, path: &String) -> std::io::Result<()> {
-        eprintln!("attempting to save...");
+    /// the label to give the selected item when it is renamed to `label`. a freshly added
+    /// item keeps the tags of the active filter
+    pub fn label_for_selection(&self, label: String) -> String {
+        let path = match self.selected_path() {
+            Some(path) => path,
+            None => return label,
+        };
+        match self.history.last() {
+            Some(Edit::Insert { path: p, .. }) if *p == path => {
+                self.with_filter_tags(&path[..path.len() - 1], label)
+            }
+            _ => label,
+        }
+    }
 
-        let file = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .create(true)
-            .open(path)
-            .expect("unable to open file");
+    pub fn get(&self, path: &[usize]) -> Option<&DooItem> {
+        let (first, rest) = path.split_first()?;
+        let mut item = self.list.get(*first)?;
+        for i in rest {
+            item = item.subtasks.get(*i)?;
+        }
+        Some(item)
+    }
 
-        let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, &self)?;
+    pub fn get_mut(&mut self, path: &[usize]) -> Option<&mut DooItem> {
+        let (first, rest) = path.split_first()?;
+        let mut item = self.list.get_mut(*first)?;
+        for i in rest {
+            item = item.subtasks.get_mut(*i)?;
+        }
+        Some(item)
+    }
 
-        Ok(())
+    /// the vector holding the item at `path`, alongside the item's index in it
+    fn siblings_mut(&mut self, path: &[usize]) -> Option<(&mut Vec<DooItem>, usize)> {
+        let (last, parent) = path.split_last()?;
+        if parent.is_empty() {
+            return Some((&mut self.list, *last));
+        }
+        self.get_mut(parent).map(|p| (&mut p.subtasks, *last))
     }
 
-    pub fn change_name(&mut self, new_name: String) {
-        self.name = Some(new_name);
+    pub fn selected_path(&self) -> Option<ItemPath> {
+        let i = self.state.selected()?;
+        self.visible().into_iter().nth(i).map(|v| v.path)
     }
 
-    pub fn add_from_label(&mut self, label: String) {
-        self.list.push(DooItem {
-            label,
-            complete: false,
+    pub fn selected_item(&self) -> Option<&DooItem> {
+        self.get(&self.selected_path()?)
+    }
+
+    /// select the item at `path`, unfolding its ancestors so that it is visible
+    pub fn select_path(&mut self, path: &[usize]) {
+        for depth in 1..path.len() {
+            if let Some(item) = self.get_mut(&path[..depth]) {
+                item.folded = false;
+            }
+        }
+
+        let index = self.visible().iter().position(|v| v.path == path);
+        if index.is_some() {
+            self.state.select(index);
+        }
+    }
+
+    /// keep completion of every ancestor of `path` consistent with its subtasks
+    fn sync_ancestors(&mut self, path: &[usize]) {
+        for depth in (1..path.len()).rev() {
+            if let Some(item) = self.get_mut(&path[..depth]) {
+                item.sync_complete();
+            }
+        }
+    }
+
+    /// completion of every ancestor of `path`, outermost first
+    fn ancestor_states(&self, path: &[usize]) -> Vec<bool> {
+        (1..path.len())
+            .filter_map(|depth| self.get(&path[..depth]).map(|item| item.complete))
+            .collect()
+    }
+
+    fn restore_ancestors(&mut self, path: &[usize], states: &[bool]) {
+        for (depth, complete) in (1..path.len()).zip(states) {
+            if let Some(item) = self.get_mut(&path[..depth]) {
+                item.complete = *complete;
+                if !complete {
+                    item.completed = None;
+                }
+            }
+        }
+    }
+
+    fn insert_at(&mut self, path: &[usize], item: DooItem) {
+        if let Some((siblings, index)) = self.siblings_mut(path) {
+            siblings.insert(index.min(siblings.len()), item);
+        }
+    }
+
+    fn remove_at(&mut self, path: &[usize]) -> Option<DooItem> {
+        let (siblings, index) = self.siblings_mut(path)?;
+        match index < siblings.len() {
+            true => Some(siblings.remove(index)),
+            false => None,
+        }
+    }
+
+    fn replace_at(&mut self, path: &[usize], item: DooItem) {
+        if let Some(slot) = self.get_mut(path) {
+            *slot = item;
+        }
+    }
+
+    fn move_child(&mut self, parent: &[usize], from: usize, to: usize) {
+        let siblings = match parent.is_empty() {
+            true => Some(&mut self.list),
+            false => self.get_mut(parent).map(|p| &mut p.subtasks),
+        };
+        if let Some(siblings) = siblings {
+            if from < siblings.len() && to < siblings.len() {
+                let item = siblings.remove(from);
+                siblings.insert(to, item);
+            }
+        }
+    }
+
+    fn record(&mut self, edit: Edit) {
+        self.history.record(edit);
+        self.modified = true;
+    }
+
+    /// record that the whole list changed from `before`, when `selected` was selected. call
+    /// it once the selection is where the edit leaves it
+    fn record_replace(&mut self, before: Vec<DooItem>, selected: Option<ItemPath>) {
+        self.record(Edit::Replace {
+            before,
+            after: self.list.clone(),
+            selected_before: selected,
+            selected_after: self.selected_path(),
         });
+    }
+
+    /// change the item at `path` in place, keeping its ancestors in sync and recording the
+    /// change so it can be undone
+    fn update_at<F>(&mut self, path: &[usize], update: F) -> Option<()>
+    where
+        F: FnOnce(&mut DooItem),
+    {
+        let ancestors = self.ancestor_states(path);
+        let before = self.get(path)?.clone();
+        update(self.get_mut(path)?);
+        self.sync_ancestors(path);
+        let after = self.get(path)?.clone();
 
-        self.select_last();
+        self.record(Edit::Update {
+            path: path.to_vec(),
+            before,
+            after,
+            ancestors,
+        });
+        Some(())
     }
 
-    pub fn add_from_item(&mut self, item: DooItem) {
-        self.list.push(item);
-        self.select_last()
+    /// select `path` again after a whole list was swapped in, or whatever now sits closest
+    fn restore_selection(&mut self, path: Option<ItemPath>) {
+        match path {
+            Some(path) => self.select_near(&path),
+            None => self.state.select(None),
+        }
     }
 
-    fn select_last(&mut self) {
-        self.state.select(Some(self.list.len() - 1));
+    /// select whatever now sits closest to `path`, after something was removed from there
+    fn select_near(&mut self, path: &[usize]) {
+        let mut candidate = path.to_vec();
+        loop {
+            if self.get(&candidate).is_some() {
+                return self.select_path(&candidate);
+            }
+            match candidate.last_mut() {
+                Some(last) if *last > 0 => *last -= 1,
+                Some(_) => {
+                    candidate.pop();
+                }
+                None => break,
+            }
+        }
+
+        let visible = self.visible().len();
+        self.state.select(visible.checked_sub(1));
     }
 
     pub fn remove(&mut self) -> Option<DooItem> {
-        match self.list.len() == 0 {
-            true => None,
-            false => {
-                if let Some(i) = self.state.selected() {
-                    let removed_item: DooItem = self.list[i].clone();
-                    if self.list.len() == 1 {
-                        self.state = ListState::default();
-                    } else if self.list.len() - 1 == i {
-                        self.state.select(Some(i - 1));
+        let path = self.selected_path()?;
+        let removed_item = self.remove_path(&path)?;
+
+        match self.get(&path) {
+            Some(_) => self.select_path(&path),
+            None => self.select_near(&path),
+        }
+
+        Some(removed_item)
+    }
+
+    /// remove the item at `path` as an undoable edit, leaving the selection and folds alone
+    pub fn remove_path(&mut self, path: &[usize]) -> Option<DooItem> {
+        let ancestors = self.ancestor_states(path);
+        let removed_item = self.remove_at(path)?;
+        self.sync_ancestors(path);
+        self.record(Edit::Delete {
+            path: path.to_vec(),
+            item: removed_item.clone(),
+            ancestors,
+        });
+        Some(removed_item)
+    }
+
+    pub fn mark_selection(&mut self) -> Result<()> {
+        let path = self
+            .selected_path()
+            .ok_or(DooError::NoSelection("mark as complete"))?;
+        self.update_at(&path, |item| item.mark());
+        Ok(())
+    }
+
+    /// mark the item at `path` and everything below it as complete or not
+    pub fn set_complete_at(&mut self, path: &[usize], complete: bool) -> Result<()> {
+        self.update_at(path, |item| item.set_complete(complete))
+            .ok_or(DooError::NoSelection("mark as complete"))
+    }
+
+    pub fn change_label_at(&mut self, path: &[usize], label: String) -> Result<()> {
+        self.update_at(path, |item| item.change_label(label))
+            .ok_or(DooError::NoSelection("change label name"))
+    }
+
+    pub fn set_notes_at(&mut self, path: &[usize], notes: String) -> Result<()> {
+        match self.get(path) {
+            None => Err(DooError::NoSelection("write notes for")),
+            Some(item) if item.notes == notes => Ok(()),
+            Some(_) => {
+                self.update_at(path, |item| item.notes = notes);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn change_label_name(&mut self, label: String) -> Result<()> {
+        self.edit_selection("change label name", |item| item.change_label(label))
+    }
+
+    /// apply `edit` to the selected item as one undoable change. `action` names the edit in
+    /// the error when nothing is selected
+    pub fn edit_selection<F>(&mut self, action: &'static str, edit: F) -> Result<()>
+    where
+        F: FnOnce(&mut DooItem),
+    {
+        let path = self.selected_path().ok_or(DooError::NoSelection(action))?;
+        self.update_at(&path, edit);
+        Ok(())
+    }
+
+    /// apply `edit` to a task that was just added, folding it into the insert so that a
+    /// single undo removes the task. anything else is recorded as a normal edit
+    pub fn edit_added<F>(&mut self, edit: F) -> Result<()>
+    where
+        F: FnOnce(&mut DooItem),
+    {
+        let path = self
+            .selected_path()
+            .ok_or(DooError::NoSelection("change label name"))?;
+        match self.history.last() {
+            Some(Edit::Insert { path: p, .. }) if *p == path => {
+                let item = self
+                    .get_mut(&path)
+                    .ok_or(DooError::NoSelection("change label name"))?;
+                edit(item);
+                let edited = item.clone();
+                if let Some(Edit::Insert { item, .. }) = self.history.last_mut() {
+                    *item = edited;
+                }
+                self.modified = true;
+                Ok(())
+            }
+            _ => self.edit_selection("change label name", edit),
+        }
+    }
+
+    /// add a subtask below the selected item and select it
+    pub fn add_subtodo(&mut self, label: String) -> Result<()> {
+        let mut path = self
+            .selected_path()
+            .ok_or(DooError::NoSelection("add a subtask to"))?;
+        let ancestors = {
+            let mut states = self.ancestor_states(&path);
+            states.extend(self.get(&path).map(|item| item.complete));
+            states
+        };
+        let label = self.with_filter_tags(&path, label);
+        let item = DooItem::new(label, false);
+        if let Some(parent) = self.get_mut(&path) {
+            parent.subtasks.push(item.clone());
+            path.push(parent.subtasks.len() - 1);
+        }
+        self.sync_ancestors(&path);
+        self.record(Edit::Insert {
+            path: path.clone(),
+            item,
+            ancestors,
+        });
+        self.select_path(&path);
+        Ok(())
+    }
+
+    /// move the item at `path` to index `to` among its siblings, keeping it selected
+    pub fn move_to(&mut self, path: &[usize], to: usize) -> Result<()> {
+        let (from, parent) = path.split_last().ok_or(DooError::NoSelection("move"))?;
+        let len = match parent.is_empty() {
+            true => self.list.len(),
+            false => self.get(parent).map_or(0, |p| p.subtasks.len()),
+        };
+        if *from >= len {
+            return Err(DooError::NoSelection("move"));
+        }
+
+        let to = to.min(len - 1);
+        if to != *from {
+            self.move_child(parent, *from, to);
+            self.record(Edit::Move {
+                parent: parent.to_vec(),
+                from: *from,
+                to,
+            });
+        }
+
+        let mut moved = parent.to_vec();
+        moved.push(to);
+        self.select_path(&moved);
+        Ok(())
+    }
+
+    /// move the selected item `offset` places up (negative) or down among its siblings
+    pub fn move_selection_by(&mut self, offset: isize) -> Result<()> {
+        let path = self.selected_path().ok_or(DooError::NoSelection("move"))?;
+        let index = path[path.len() - 1].saturating_add_signed(offset);
+        self.move_to(&path, index)
+    }
+
+    /// move the selected item to index `to` among its siblings, clamped to the last place
+    pub fn move_selection_to(&mut self, to: usize) -> Result<()> {
+        let path = self.selected_path().ok_or(DooError::NoSelection("move"))?;
+        self.move_to(&path, to)
+    }
+
+    /// sort every level of the tree by `key`, keeping subtasks under their parents. the sort
+    /// is stable, so items that compare equal keep their order
+    pub fn sort(&mut self, key: SortKey) {
+        fn sort(items: &mut Vec<DooItem>, key: SortKey, selected: Option<&[usize]>) -> ItemPath {
+            let mut indexed: Vec<(usize, DooItem)> = items.drain(..).enumerate().collect();
+            indexed.sort_by(|(_, a), (_, b)| key.compare(a, b));
+
+            let mut moved = Vec::new();
+            for (i, (old, mut item)) in indexed.into_iter().enumerate() {
+                let rest = selected
+                    .filter(|s| s.first() == Some(&old))
+                    .map(|s| &s[1..]);
+                let below = sort(&mut item.subtasks, key, rest);
+                if rest.is_some() {
+                    moved = [vec![i], below].concat();
+                }
+                items.push(item);
+            }
+            moved
+        }
+
+        let before = self.list.clone();
+        let selected = self.selected_path();
+        let moved = sort(&mut self.list, key, selected.as_deref());
+        if !moved.is_empty() {
+            self.select_path(&moved);
+        }
+        if self.list != before {
+            self.record_replace(before, selected);
+        }
+    }
+
+    /// add `items` to the end of the list as a single edit, selecting the first of them
+    pub fn append(&mut self, items: Vec<DooItem>) {
+        if items.is_empty() {
+            return;
+        }
+        let before = self.list.clone();
+        let selected = self.selected_path();
+        let first = self.list.len();
+        self.list.extend(items);
+        self.select_path(&[first]);
+        self.record_replace(before, selected);
+    }
+
+    /// insert `items` next to the selected item, below it or `above` it, as one undoable
+    /// edit, selecting the first of them. with nothing selected they go at the end
+    pub fn paste(&mut self, items: Vec<DooItem>, above: bool) {
+        if items.is_empty() {
+            return;
+        }
+        let (parent, index) = match self.selected_path() {
+            Some(mut path) => {
+                let last = path.pop().unwrap_or(0);
+                let index = match above {
+                    true => last,
+                    false => last + 1,
+                };
+                (path, index)
+            }
+            None => (Vec::new(), self.list.len()),
+        };
+
+        let before = self.list.clone();
+        let selected = self.selected_path();
+        let siblings = match parent.is_empty() {
+            true => Some(&mut self.list),
+            false => self.get_mut(&parent).map(|p| &mut p.subtasks),
+        };
+        if let Some(siblings) = siblings {
+            let index = index.min(siblings.len());
+            siblings.splice(index..index, items);
+            let first = [parent, vec![index]].concat();
+            self.sync_ancestors(&first);
+            self.select_path(&first);
+            self.record_replace(before, selected);
+        }
+    }
+
+    /// swap in a whole new set of items as one undoable edit, as when the file changed on
+    /// disk. the selection stays as near to where it was as the new items allow
+    pub fn replace_list(&mut self, items: Vec<DooItem>) {
+        if self.list == items {
+            return;
+        }
+        let selected = self.selected_path();
+        let before = std::mem::replace(&mut self.list, items);
+        self.select_near(&selected.clone().unwrap_or_default());
+        self.record_replace(before, selected);
+    }
+
+    /// apply `edit` to every item in `paths` as one undoable change. `action` names the edit
+    /// in the error when there is nothing to edit
+    pub fn edit_paths<F>(
+        &mut self,
+        action: &'static str,
+        paths: &[ItemPath],
+        mut edit: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&mut DooItem),
+    {
+        if paths.is_empty() {
+            return Err(DooError::NoSelection(action));
+        }
+        let before = self.list.clone();
+        for path in paths {
+            if let Some(item) = self.get_mut(path) {
+                edit(item);
+            }
+            self.sync_ancestors(path);
+        }
+        if self.list != before {
+            self.record_replace(before, self.selected_path());
+        }
+        Ok(())
+    }
+
+    /// mark every item in `paths` as complete, or all of them as pending when they already are
+    pub fn mark_paths(&mut self, paths: &[ItemPath]) -> Result<()> {
+        let complete = !paths
+            .iter()
+            .all(|path| self.get(path).is_none_or(|item| item.complete));
+        self.edit_paths("mark as complete", paths, |item| {
+            item.set_complete(complete)
+        })
+    }
+
+    /// the items in `paths` along with their subtasks, leaving out those already below
+    /// another one of them
+    pub fn items_at(&self, paths: &[ItemPath]) -> Vec<DooItem> {
+        outermost(paths)
+            .into_iter()
+            .filter_map(|path| self.get(path).cloned())
+            .collect()
+    }
+
+    /// remove every item in `paths` as one undoable change, returning them in list order
+    pub fn remove_paths(&mut self, paths: &[ItemPath]) -> Result<Vec<DooItem>> {
+        let outer = outermost(paths);
+        let first = match outer.first() {
+            Some(first) => first.to_vec(),
+            None => return Err(DooError::NoSelection("delete")),
+        };
+        let before = self.list.clone();
+        let selected = self.selected_path();
+        let mut removed = Vec::new();
+        // last first, so that the paths still to go stay where they were
+        for path in outer.iter().rev() {
+            if let Some(item) = self.remove_at(path) {
+                self.sync_ancestors(path);
+                removed.push(item);
+            }
+        }
+        removed.reverse();
+        self.select_near(&first);
+        self.record_replace(before, selected);
+        Ok(removed)
+    }
+
+    /// move every item in `paths` `offset` places up (negative) or down among its siblings as
+    /// one undoable change. items moving the same way keep their order, and stop at the ends
+    /// of their level. returns where `paths` are now, the cursor follows its item
+    pub fn move_paths_by(&mut self, paths: &[ItemPath], offset: isize) -> Result<Vec<ItemPath>> {
+        let outer = outermost(paths);
+        if outer.is_empty() {
+            return Err(DooError::NoSelection("move"));
+        }
+
+        // the moving indices under each parent
+        let mut groups: std::collections::BTreeMap<ItemPath, BTreeSet<usize>> =
+            std::collections::BTreeMap::new();
+        for path in &outer {
+            if let Some((last, parent)) = path.split_last() {
+                groups.entry(parent.to_vec()).or_default().insert(*last);
+            }
+        }
+
+        // for each parent, the new index of every child
+        let mut moved = std::collections::BTreeMap::new();
+        let before = self.list.clone();
+        // deepest first, so that the parents still to go are found where they were
+        for (parent, picked) in groups.into_iter().rev() {
+            let len = match parent.is_empty() {
+                true => self.list.len(),
+                false => self.get(&parent).map_or(0, |p| p.subtasks.len()),
+            };
+            let mut order: Vec<usize> = (0..len).collect();
+            for _ in 0..offset.unsigned_abs().min(len) {
+                match offset < 0 {
+                    true => {
+                        for i in 1..len {
+                            if picked.contains(&order[i]) && !picked.contains(&order[i - 1]) {
+                                order.swap(i, i - 1);
+                            }
+                        }
+                    }
+                    false => {
+                        for i in (0..len.saturating_sub(1)).rev() {
+                            if picked.contains(&order[i]) && !picked.contains(&order[i + 1]) {
+                                order.swap(i, i + 1);
+                            }
+                        }
                     }
-                    self.list.remove(i);
-                    return Some(removed_item);
                 }
+            }
+
+            let siblings = match parent.is_empty() {
+                true => Some(&mut self.list),
+                false => self.get_mut(&parent).map(|p| &mut p.subtasks),
+            };
+            if let Some(siblings) = siblings {
+                let mut old: Vec<Option<DooItem>> = siblings.drain(..).map(Some).collect();
+                siblings.extend(order.iter().filter_map(|i| old[*i].take()));
+            }
+            let mut new_index = vec![0; len];
+            for (new, old) in order.into_iter().enumerate() {
+                new_index[old] = new;
+            }
+            moved.insert(parent, new_index);
+        }
+
+        let follow = |path: &[usize]| -> ItemPath {
+            (0..path.len())
+                .map(|depth| {
+                    moved
+                        .get(&path[..depth])
+                        .and_then(|new_index| new_index.get(path[depth]))
+                        .map_or(path[depth], |new| *new)
+                })
+                .collect()
+        };
+        let cursor = self.selected_path();
+        if let Some(cursor) = &cursor {
+            self.select_path(&follow(cursor));
+        }
+        if self.list != before {
+            self.record_replace(before, cursor);
+        }
+        Ok(paths.iter().map(|path| follow(path)).collect())
+    }
+
+    /// revert the most recent edit. returns false when there is nothing to undo
+    pub fn undo(&mut self) -> bool {
+        match self.history.undo() {
+            Some(edit) => {
+                self.revert(edit);
+                self.modified = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// replay the most recently undone edit. returns false when there is nothing to redo
+    pub fn redo(&mut self) -> bool {
+        match self.history.redo() {
+            Some(edit) => {
+                self.apply(edit);
+                self.modified = true;
+                true
+            }
+            None => false,
+        }
+    }
 
-                return None;
+    fn revert(&mut self, edit: Edit) {
+        match edit {
+            Edit::Insert {
+                path, ancestors, ..
+            } => {
+                self.remove_at(&path);
+                self.restore_ancestors(&path, &ancestors);
+                self.select_near(&path);
+            }
+            Edit::Delete {
+                path,
+                item,
+                ancestors,
+            } => {
+                self.insert_at(&path, item);
+                self.restore_ancestors(&path, &ancestors);
+                self.select_path(&path);
+            }
+            Edit::Update {
+                path,
+                before,
+                ancestors,
+                ..
+            } => {
+                self.replace_at(&path, before);
+                self.restore_ancestors(&path, &ancestors);
+                self.select_path(&path);
+            }
+            Edit::Rename { before, .. } => self.name = before,
+            Edit::Replace {
+                before,
+                selected_before,
+                ..
+            } => {
+                self.list = before;
+                self.restore_selection(selected_before);
+            }
+            Edit::Move { parent, from, to } => {
+                self.move_child(&parent, to, from);
+                self.select_path(&[parent, vec![from]].concat());
+            }
+        }
+    }
+
+    fn apply(&mut self, edit: Edit) {
+        match edit {
+            Edit::Insert { path, item, .. } => {
+                self.insert_at(&path, item);
+                self.sync_ancestors(&path);
+                self.select_path(&path);
+            }
+            Edit::Delete { path, .. } => {
+                self.remove_at(&path);
+                self.sync_ancestors(&path);
+                self.select_near(&path);
+            }
+            Edit::Update { path, after, .. } => {
+                self.replace_at(&path, after);
+                self.sync_ancestors(&path);
+                self.select_path(&path);
+            }
+            Edit::Rename { after, .. } => self.name = after,
+            Edit::Replace {
+                after,
+                selected_after,
+                ..
+            } => {
+                self.list = after;
+                self.restore_selection(selected_after);
+            }
+            Edit::Move { parent, from, to } => {
+                self.move_child(&parent, from, to);
+                self.select_path(&[parent, vec![to]].concat());
             }
         }
     }
 
-    pub fn mark_selection(&mut self) -> Result<(), &'static str> {
-        match self.state.selected() {
-            None => Err("no selection to mark as complete"),
-            Some(i) => Ok(self.list[i].mark()),
+    /// fold the selected item, or move to its parent if there is nothing to fold
+    pub fn fold_selection(&mut self) {
+        if let Some(path) = self.selected_path() {
+            match self.get_mut(&path) {
+                Some(item) if item.has_subtasks() && !item.folded => item.folded = true,
+                _ if path.len() > 1 => self.select_path(&path[..path.len() - 1]),
+                _ => {}
+            }
         }
     }
 
-    pub fn change_label_name(&mut self, label: String) -> Result<(), &'static str> {
-        match self.state.selected() {
-            None => Err("no selection to change label name"),
-            Some(i) => Ok(self.list[i].change_label(label)),
+    pub fn unfold_selection(&mut self) {
+        if let Some(item) = self.selected_path().and_then(|path| self.get_mut(&path)) {
+            item.folded = false;
         }
     }
+}
 
-    pub fn add_subtodo() {
-        todo!()
+/// `paths` without those lying below another one of them, in list order
+fn outermost(paths: &[ItemPath]) -> Vec<&ItemPath> {
+    let mut paths: Vec<&ItemPath> = paths.iter().collect();
+    paths.sort();
+    paths.dedup();
+    let mut outer: Vec<&ItemPath> = Vec::new();
+    for path in paths {
+        // sorted, so the descendants of a kept path come right after it
+        match outer.last() {
+            Some(last) if path.starts_with(last) => {}
+            _ => outer.push(path),
+        }
     }
+    outer
 }
 
 impl lists::Navigate for DooList {
     fn previous(&mut self) {
-        if self.list.len() != 0 {
+        if !self.visible().is_empty() {
             let i = match self.state.selected() {
-                Some(i) => match i == 0 {
-                    true => i,
-                    false => i - 1,
-                },
+                Some(i) => i.saturating_sub(1),
                 None => 0,
             };
             self.state.select(Some(i));
@@ -156,12 +1103,10 @@ impl lists::Navigate for DooList {
     }
 
     fn next(&mut self) {
-        if self.list.len() != 0 {
+        let len = self.visible().len();
+        if len != 0 {
             let i = match self.state.selected() {
-                Some(i) => match i >= self.list.len() - 1 {
-                    true => i,
-                    false => i + 1,
-                },
+                Some(i) => (i + 1).min(len - 1),
                 None => 0,
             };
             self.state.select(Some(i));
@@ -170,4 +1115,362 @@ impl lists::Navigate for DooList {
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use crate::app::backup;
+    use crate::app::lists::Navigate;
+
+    fn nested() -> DooList {
+        let mut doolist = DooList::new();
+        doolist.add_from_label(String::from("plan"));
+        doolist.add_subtodo(String::from("design")).unwrap();
+        doolist.add_subtodo(String::from("sketch")).unwrap();
+        doolist.select_path(&[0]);
+        doolist.add_subtodo(String::from("build")).unwrap();
+        doolist.add_from_label(String::from("ship"));
+        doolist
+    }
+
+    #[test]
+    fn visible_walks_tree_depth_first() {
+        let doolist = nested();
+        let paths: Vec<ItemPath> = doolist.visible().into_iter().map(|v| v.path).collect();
+        assert_eq!(
+            paths,
+            vec![vec![0], vec![0, 0], vec![0, 0, 0], vec![0, 1], vec![1]]
+        );
+    }
+
+    #[test]
+    fn folding_hides_subtasks_from_navigation() {
+        let mut doolist = nested();
+        doolist.select_path(&[0]);
+        doolist.fold_selection();
+        assert_eq!(doolist.visible().len(), 2);
+
+        doolist.next();
+        assert_eq!(doolist.selected_path(), Some(vec![1]));
+    }
+
+    #[test]
+    fn completing_every_subtask_completes_parent() {
+        let mut doolist = nested();
+        doolist.select_path(&[0, 0, 0]);
+        doolist.mark_selection().unwrap();
+        assert!(doolist.get(&[0, 0]).unwrap().complete);
+        assert!(!doolist.get(&[0]).unwrap().complete);
+
+        doolist.select_path(&[0, 1]);
+        doolist.mark_selection().unwrap();
+        assert!(doolist.get(&[0]).unwrap().complete);
+
+        doolist.mark_selection().unwrap();
+        assert!(!doolist.get(&[0]).unwrap().complete);
+    }
+
+    #[test]
+    fn priority_steps_one_letter_at_a_time() {
+        let mut item = DooItem::new(String::from("plan"), false);
+        item.raise_priority();
+        assert_eq!(item.priority, Some('C'));
+        item.raise_priority();
+        item.raise_priority();
+        item.raise_priority();
+        assert_eq!(item.priority, Some('A'));
+        item.lower_priority();
+        assert_eq!(item.priority, Some('B'));
+        item.lower_priority();
+        item.lower_priority();
+        assert_eq!(item.priority, None);
+
+        // letters from todo.txt past 'C'
+        item.priority = Some('D');
+        item.lower_priority();
+        assert_eq!(item.priority, Some('E'));
+        item.raise_priority();
+        item.raise_priority();
+        assert_eq!(item.priority, Some('C'));
+        item.priority = Some('Z');
+        item.raise_priority();
+        assert_eq!(item.priority, Some('Y'));
+        item.lower_priority();
+        item.lower_priority();
+        assert_eq!(item.priority, None);
+    }
+
+    #[test]
+    fn edits_mark_list_as_modified() {
+        let mut doolist = DooList::new();
+        assert!(!doolist.modified);
+        doolist.add_from_label(String::from("a"));
+        assert!(doolist.modified);
+
+        doolist.modified = false;
+        doolist.next();
+        doolist.fold_selection();
+        assert!(!doolist.modified);
+
+        doolist.mark_selection().unwrap();
+        assert!(doolist.modified);
+    }
+
+    #[test]
+    fn undo_restores_deleted_item_in_place() {
+        let mut doolist = nested();
+        doolist.select_path(&[0, 0]);
+        let removed = doolist.remove().unwrap();
+        assert_eq!(removed.label, "design");
+        assert_eq!(doolist.get(&[0, 0]).unwrap().label, "build");
+
+        assert!(doolist.undo());
+        assert_eq!(doolist.get(&[0, 0]).unwrap().label, "design");
+        assert_eq!(doolist.selected_path(), Some(vec![0, 0]));
+
+        assert!(doolist.redo());
+        assert_eq!(doolist.get(&[0, 0]).unwrap().label, "build");
+        assert!(!doolist.redo());
+    }
+
+    #[test]
+    fn removing_by_path_leaves_folds_alone() {
+        let mut doolist = nested();
+        doolist.select_path(&[1]);
+        doolist.select_path(&[0]);
+        doolist.fold_selection();
+        doolist.modified = false;
+
+        let removed = doolist.remove_path(&[0, 0, 0]).unwrap();
+        assert_eq!(removed.label, "sketch");
+        assert!(doolist.get(&[0]).unwrap().folded);
+        assert_eq!(doolist.selected_path(), Some(vec![0]));
+        assert!(doolist.modified);
+        assert!(doolist.remove_path(&[0, 5]).is_none());
+
+        assert!(doolist.undo());
+        assert_eq!(doolist.get(&[0, 0, 0]).unwrap().label, "sketch");
+    }
+
+    #[test]
+    fn undo_mark_restores_parent_completion() {
+        let mut doolist = nested();
+        doolist.select_path(&[0, 0]);
+        doolist.mark_selection().unwrap();
+        doolist.select_path(&[0, 1]);
+        doolist.mark_selection().unwrap();
+        assert!(doolist.get(&[0]).unwrap().complete);
+
+        doolist.undo();
+        assert!(!doolist.get(&[0]).unwrap().complete);
+        assert!(doolist.get(&[0, 0]).unwrap().complete);
+    }
+
+    #[test]
+    fn relabeling_a_new_item_undoes_in_one_step() {
+        let mut doolist = nested();
+        doolist.add_from_label(String::from("-- new task --"));
+        doolist
+            .edit_added(|item| item.change_label(String::from("review")))
+            .unwrap();
+
+        doolist.undo();
+        assert_eq!(doolist.list.len(), 2);
+        doolist.redo();
+        assert_eq!(doolist.list[2].label, "review");
+    }
+
+    #[test]
+    fn later_edits_to_a_new_item_undo_on_their_own() {
+        let mut doolist = nested();
+        doolist.add_from_label(String::from("-- new task --"));
+        doolist
+            .edit_added(|item| item.change_label(String::from("review")))
+            .unwrap();
+        doolist
+            .edit_selection("change priority of", |item| item.raise_priority())
+            .unwrap();
+
+        doolist.undo();
+        assert_eq!(doolist.list.len(), 3);
+        assert_eq!(doolist.list[2].label, "review");
+        assert_eq!(doolist.list[2].priority, None);
+        doolist.undo();
+        assert_eq!(doolist.list.len(), 2);
+    }
+
+    #[test]
+    fn history_depth_drops_oldest_edits() {
+        let mut doolist = DooList::new();
+        doolist.history.set_depth(2);
+        for label in ["a", "b", "c"] {
+            doolist.add_from_label(String::from(label));
+        }
+
+        assert!(doolist.undo());
+        assert!(doolist.undo());
+        assert!(!doolist.undo());
+        assert_eq!(doolist.list.len(), 1);
+    }
+
+    #[test]
+    fn moves_stay_within_siblings_and_keep_selection() {
+        let mut doolist = nested();
+        doolist.select_path(&[0, 1]);
+        doolist.move_selection_by(-1).unwrap();
+        assert_eq!(doolist.get(&[0, 0]).unwrap().label, "build");
+        assert_eq!(doolist.selected_path(), Some(vec![0, 0]));
+
+        doolist.move_selection_by(-1).unwrap();
+        assert_eq!(doolist.selected_path(), Some(vec![0, 0]));
+
+        doolist.select_path(&[0]);
+        doolist.move_selection_to(usize::MAX).unwrap();
+        assert_eq!(doolist.list[1].label, "plan");
+        assert_eq!(doolist.selected_path(), Some(vec![1]));
+
+        doolist.undo();
+        assert_eq!(doolist.list[0].label, "plan");
+        assert_eq!(doolist.selected_path(), Some(vec![0]));
+    }
+
+    #[test]
+    fn old_files_without_subtasks_still_load() {
+        let doolist: DooList =
+            serde_json::from_str(r#"{"name":"x","list":[{"label":"a","complete":true}]}"#).unwrap();
+        assert!(doolist.list[0].subtasks.is_empty());
+    }
+
+    #[test]
+    fn sort_by_priority_keeps_subtasks_and_selection() {
+        let mut doolist = nested();
+        doolist.select_path(&[1]);
+        doolist
+            .edit_selection("change priority of", |item| item.raise_priority())
+            .unwrap();
+        doolist.select_path(&[0, 1]);
+        doolist
+            .edit_selection("change priority of", |item| item.priority = Some('A'))
+            .unwrap();
+
+        doolist.sort(SortKey::Priority);
+        let labels: Vec<&str> = doolist.list.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, vec!["ship", "plan"]);
+        assert_eq!(doolist.list[1].subtasks[0].label, "build");
+        assert_eq!(doolist.selected_path(), Some(vec![1, 0]));
+
+        assert!(doolist.undo());
+        assert_eq!(doolist.list[0].label, "plan");
+        assert_eq!(doolist.list[0].subtasks[0].label, "design");
+        assert_eq!(doolist.selected_path(), Some(vec![0, 1]));
+        assert!(doolist.redo());
+        assert_eq!(doolist.selected_path(), Some(vec![1, 0]));
+    }
+
+    #[test]
+    fn undoing_a_bulk_delete_puts_the_cursor_back() {
+        let mut doolist = nested();
+        doolist.select_path(&[0, 1]);
+        doolist.remove_paths(&[vec![0, 1], vec![1]]).unwrap();
+        assert_eq!(doolist.selected_path(), Some(vec![0, 0]));
+
+        assert!(doolist.undo());
+        assert_eq!(doolist.selected_path(), Some(vec![0, 1]));
+        assert!(doolist.redo());
+        assert_eq!(doolist.selected_path(), Some(vec![0, 0]));
+    }
+
+    #[test]
+    fn filter_shows_tagged_items_under_their_parents() {
+        let mut doolist = nested();
+        doolist.select_path(&[0, 0, 0]);
+        doolist
+            .change_label_name(String::from("sketch #Work"))
+            .unwrap();
+        assert!(doolist.get(&[0, 0, 0]).unwrap().tags.contains("#work"));
+
+        doolist.set_filter(vec![String::from("#work")]);
+        let paths: Vec<ItemPath> = doolist.visible().into_iter().map(|v| v.path).collect();
+        assert_eq!(paths, vec![vec![0], vec![0, 0], vec![0, 0, 0]]);
+        assert_eq!(doolist.selected_path(), Some(vec![0, 0, 0]));
+
+        // new items take the filter's tags so they stay in view, also after relabeling
+        doolist.add_from_label(String::from("-- new task --"));
+        assert_eq!(doolist.selected_path(), Some(vec![2]));
+        let label = doolist.label_for_selection(String::from("call bob"));
+        doolist.change_label_name(label).unwrap();
+        assert_eq!(doolist.list[2].label, "call bob #work");
+
+        doolist.set_filter(Vec::new());
+        assert_eq!(doolist.visible().len(), 6);
+    }
+
+    #[test]
+    fn visual_selection_acts_on_every_picked_task_at_once() {
+        use crate::app::visual::Selection;
+
+        let mut doolist = nested();
+        // from design down to build, then drop build and pick ship
+        doolist.state.select(Some(1));
+        let mut selection = Selection::new(&doolist);
+        doolist.next();
+        doolist.next();
+        selection.toggle(&doolist);
+        doolist.next();
+        selection.toggle(&doolist);
+        let paths = selection.paths(&doolist);
+        assert_eq!(paths, vec![vec![0, 0], vec![0, 0, 0], vec![1]]);
+
+        doolist.mark_paths(&paths).unwrap();
+        assert!(doolist.get(&[0, 0, 0]).unwrap().complete);
+        assert!(doolist.get(&[1]).unwrap().complete);
+        assert!(!doolist.get(&[0]).unwrap().complete);
+        doolist.mark_paths(&paths).unwrap();
+        assert!(!doolist.get(&[1]).unwrap().complete);
+
+        // sketch comes along with design
+        let items = doolist.items_at(&paths);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].subtasks[0].label, "sketch");
+
+        let moved = doolist.move_paths_by(&[vec![0, 1], vec![1]], -1).unwrap();
+        assert_eq!(moved, vec![vec![1, 0], vec![0]]);
+        assert_eq!(doolist.get(&[0]).unwrap().label, "ship");
+        assert_eq!(doolist.get(&[1, 0]).unwrap().label, "build");
+        assert!(doolist.undo());
+        assert_eq!(doolist.get(&[0]).unwrap().label, "plan");
+
+        let before = doolist.list.clone();
+        let removed = doolist.remove_paths(&paths).unwrap();
+        assert_eq!(removed.len(), 2);
+        assert_eq!(doolist.list.len(), 1);
+        assert_eq!(doolist.get(&[0, 0]).unwrap().label, "build");
+        // a single undo brings every deleted task back
+        assert!(doolist.undo());
+        assert!(doolist.list == before);
+    }
+
+    #[test]
+    fn pasting_puts_tasks_after_or_above_the_selection() {
+        let from = nested();
+        let mut to = DooList::new();
+        to.add_from_label(String::from("inbox"));
+        let mut items = from.items_at(&[vec![1]]);
+        items.extend(from.items_at(&[vec![0, 1]]));
+        to.paste(items, false);
+        assert_eq!(to.visible().len(), 3);
+        assert_eq!(to.get(&[1]).unwrap().label, "ship");
+        assert_eq!(to.get(&[2]).unwrap().label, "build");
+        assert_eq!(to.selected_path(), Some(vec![1]));
+
+        to.select_path(&[0]);
+        to.paste(from.items_at(&[vec![1]]), true);
+        assert_eq!(to.list.len(), 4);
+        assert_eq!(to.get(&[0]).unwrap().label, "ship");
+
+        // subtasks come along, and the paste undoes in one step
+        to.select_path(&[1]);
+        to.paste(from.items_at(&[vec![0]]), true);
+        assert_eq!(to.get(&[1, 0, 0]).unwrap().label, "sketch");
+        assert!(to.undo());
+        assert_eq!(to.get(&[1]).unwrap().label, "inbox");
+    }
+}
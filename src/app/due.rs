@@ -0,0 +1,301 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// due dates given without a time of day are due at the end of that day
+pub fn end_of_day() -> NaiveTime {
+    NaiveTime::from_hms_opt(23, 59, 0).unwrap()
+}
+
+pub fn is_all_day(due: &NaiveDateTime) -> bool {
+    due.time() == end_of_day()
+}
+
+/// parse a loose, natural language due date such as "tomorrow 5pm", "fri", "next mon",
+/// "in 3 days", "2024-05-01 17:30" or "may 3", relative to `now`
+pub fn parse(input: &str, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let input = input.trim().to_lowercase();
+    let words: Vec<&str> = input
+        .split_whitespace()
+        .filter(|w| *w != "at" && *w != "on")
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+
+    // "in 3 days", "in 2h", "in 1 week"
+    if words[0] == "in" {
+        return now.checked_add_signed(parse_offset(&words[1..])?);
+    }
+
+    let today = now.date();
+    let mut date: Option<NaiveDate> = None;
+    let mut time: Option<NaiveTime> = None;
+    let mut next = false;
+    let mut i = 0;
+
+    while i < words.len() {
+        let word = words[i];
+        match word {
+            "today" | "tod" => date = Some(today),
+            "tomorrow" | "tmr" | "tom" => date = Some(today + Duration::days(1)),
+            "next" => next = true,
+            "noon" => time = NaiveTime::from_hms_opt(12, 0, 0),
+            "midnight" => time = NaiveTime::from_hms_opt(0, 0, 0),
+            _ => {
+                if let Some(weekday) = parse_weekday(word) {
+                    date = Some(next_weekday(today, weekday, next));
+                } else if let Some(month) = parse_month(word) {
+                    // "may 3"
+                    let day = words.get(i + 1)?.parse::<u32>().ok()?;
+                    date = Some(month_day(today, month, day)?);
+                    i += 1;
+                } else if let Ok(d) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+                    date = Some(d);
+                } else if let Some(t) = parse_time(word) {
+                    time = Some(t);
+                } else if let (Ok(day), Some(month)) = (
+                    word.parse::<u32>(),
+                    words.get(i + 1).and_then(|w| parse_month(w)),
+                ) {
+                    // "3 may"
+                    date = Some(month_day(today, month, day)?);
+                    i += 1;
+                } else {
+                    return None;
+                }
+            }
+        }
+        i += 1;
+    }
+
+    match (date, time) {
+        (None, None) => None,
+        (Some(date), None) => Some(date.and_time(end_of_day())),
+        (date, Some(time)) => Some(date.unwrap_or(today).and_time(time)),
+    }
+}
+
+/// pull an inline '@due(...)' token out of a label, returning the label without it and the
+/// parsed date. tokens that do not parse are left in the label untouched
+pub fn extract(label: &str, now: NaiveDateTime) -> (String, Option<NaiveDateTime>) {
+    static TOKEN: OnceLock<Regex> = OnceLock::new();
+    let token = TOKEN.get_or_init(|| Regex::new(r"\s*@due\(([^)]*)\)").unwrap());
+    let captures = match token.captures(label) {
+        Some(captures) => captures,
+        None => return (label.to_string(), None),
+    };
+
+    match parse(&captures[1], now) {
+        Some(due) => {
+            let whole = captures.get(0).unwrap();
+            let stripped = format!("{}{}", &label[..whole.start()], &label[whole.end()..]);
+            (stripped.trim().to_string(), Some(due))
+        }
+        None => (label.to_string(), None),
+    }
+}
+
+/// a short description of when `due` is relative to `now`, e.g. "today 17:00", "in 3d" or
+/// "2h ago"
+pub fn relative(due: &NaiveDateTime, now: NaiveDateTime) -> String {
+    let days = (due.date() - now.date()).num_days();
+
+    if is_all_day(due) {
+        return match days {
+            0 => String::from("today"),
+            1 => String::from("tomorrow"),
+            -1 => String::from("yesterday"),
+            d if d > 0 => format!("in {}d", d),
+            d => format!("{}d ago", -d),
+        };
+    }
+
+    let minutes = (*due - now).num_minutes();
+    match days {
+        0 if minutes.abs() < 60 => match minutes >= 0 {
+            true => format!("in {}m", minutes),
+            false => format!("{}m ago", -minutes),
+        },
+        0 => format!("today {}", due.format("%H:%M")),
+        1 => format!("tomorrow {}", due.format("%H:%M")),
+        d if d > 0 => format!("in {}d", d),
+        d => format!("{}d ago", -d),
+    }
+}
+
+fn parse_offset(words: &[&str]) -> Option<Duration> {
+    // accept both "3 days" and "3days"/"3d"
+    let joined = words.join("");
+    let split = joined.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = joined.split_at(split);
+    let amount = amount.parse::<i64>().ok()?;
+
+    // amounts too large for a duration are not a date, rather than a panic
+    match unit {
+        "m" | "min" | "mins" | "minute" | "minutes" => Duration::try_minutes(amount),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Duration::try_hours(amount),
+        "d" | "day" | "days" => Duration::try_days(amount),
+        "w" | "wk" | "week" | "weeks" => Duration::try_weeks(amount),
+        _ => None,
+    }
+}
+
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    let (digits, offset) = match (word.strip_suffix("am"), word.strip_suffix("pm")) {
+        (Some(d), _) => (d, Some(0)),
+        (_, Some(d)) => (d, Some(12)),
+        _ => (word, None),
+    };
+
+    let (hour, minute) = match digits.split_once(':') {
+        Some((h, m)) => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+        // a bare number is only a time when it has am/pm, otherwise it could be a day
+        None if offset.is_some() => (digits.parse::<u32>().ok()?, 0),
+        None => return None,
+    };
+
+    let hour = match offset {
+        Some(_) if hour == 0 || hour > 12 => return None,
+        Some(offset) => hour % 12 + offset,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thur" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn parse_month(word: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    if word.len() < 3 {
+        return None;
+    }
+    MONTHS
+        .iter()
+        .position(|m| word.starts_with(m))
+        .map(|i| i as u32 + 1)
+}
+
+/// the coming `weekday`, today included. "next" skips ahead to the one after that
+fn next_weekday(today: NaiveDate, weekday: Weekday, next: bool) -> NaiveDate {
+    let ahead = (7 + weekday.num_days_from_monday() as i64
+        - today.weekday().num_days_from_monday() as i64)
+        % 7;
+    let ahead = match next {
+        true => ahead + 7,
+        false => ahead,
+    };
+    today + Duration::days(ahead)
+}
+
+/// the next `month`/`day`, rolling over to next year once this year's has passed
+fn month_day(today: NaiveDate, month: u32, day: u32) -> Option<NaiveDate> {
+    let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+    match date < today {
+        true => NaiveDate::from_ymd_opt(today.year() + 1, month, day),
+        false => Some(date),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a wednesday morning
+    fn now() -> NaiveDateTime {
+        NaiveDateTime::parse_from_str("2024-05-01 09:00", "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn due(input: &str) -> Option<String> {
+        parse(input, now()).map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+    }
+
+    #[test]
+    fn loose_dates_parse_relative_to_now() {
+        assert_eq!(due("tomorrow 5pm").as_deref(), Some("2024-05-02 17:00"));
+        assert_eq!(due("today").as_deref(), Some("2024-05-01 23:59"));
+        assert_eq!(due("noon").as_deref(), Some("2024-05-01 12:00"));
+        assert_eq!(due("in 3 days").as_deref(), Some("2024-05-04 09:00"));
+        assert_eq!(due("in 2h").as_deref(), Some("2024-05-01 11:00"));
+        assert_eq!(due("2024-06-01 17:30").as_deref(), Some("2024-06-01 17:30"));
+    }
+
+    #[test]
+    fn weekdays_and_months_look_ahead() {
+        // today counts as the coming wednesday, "next" skips a week
+        assert_eq!(due("wed").as_deref(), Some("2024-05-01 23:59"));
+        assert_eq!(due("next wed").as_deref(), Some("2024-05-08 23:59"));
+        assert_eq!(due("fri at 9am").as_deref(), Some("2024-05-03 09:00"));
+        assert_eq!(due("may 3").as_deref(), Some("2024-05-03 23:59"));
+        assert_eq!(due("3 may").as_deref(), Some("2024-05-03 23:59"));
+        // a day already passed this year is next year's
+        assert_eq!(due("apr 30").as_deref(), Some("2025-04-30 23:59"));
+    }
+
+    #[test]
+    fn nonsense_is_not_a_date() {
+        for input in [
+            "",
+            "soon",
+            "in",
+            "in 3 fortnights",
+            "13pm",
+            "feb 30",
+            "5",
+            "may",
+        ] {
+            assert_eq!(due(input), None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn offsets_past_the_calendar_are_not_a_date() {
+        // too long for a duration, then too far out for a date
+        assert_eq!(due("in 999999999 days"), None);
+        assert_eq!(due("in 99999999d"), None);
+        assert_eq!(due("in 999999999999 minutes"), None);
+
+        let (label, due) = extract("call mom @due(in 999999999d)", now());
+        assert_eq!(label, "call mom @due(in 999999999d)");
+        assert_eq!(due, None);
+    }
+
+    #[test]
+    fn due_token_is_pulled_out_of_label() {
+        let (label, due) = extract("call mom @due(tomorrow 5pm) +family", now());
+        assert_eq!(label, "call mom +family");
+        assert_eq!(due.unwrap().to_string(), "2024-05-02 17:00:00");
+
+        // a token that does not parse stays where it is
+        let (label, due) = extract("call mom @due(someday)", now());
+        assert_eq!(label, "call mom @due(someday)");
+        assert_eq!(due, None);
+        assert_eq!(extract("call mom", now()), (String::from("call mom"), None));
+    }
+
+    #[test]
+    fn relative_descriptions() {
+        let at = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        assert_eq!(relative(&at("2024-05-01 23:59"), now()), "today");
+        assert_eq!(relative(&at("2024-04-30 23:59"), now()), "yesterday");
+        assert_eq!(relative(&at("2024-05-01 09:30"), now()), "in 30m");
+        assert_eq!(relative(&at("2024-05-01 08:15"), now()), "45m ago");
+        assert_eq!(relative(&at("2024-05-01 17:00"), now()), "today 17:00");
+        assert_eq!(relative(&at("2024-05-02 17:00"), now()), "tomorrow 17:00");
+        assert_eq!(relative(&at("2024-05-05 10:00"), now()), "in 4d");
+        assert_eq!(relative(&at("2024-04-28 10:00"), now()), "3d ago");
+    }
+}
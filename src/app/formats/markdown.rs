@@ -0,0 +1,320 @@
+// checklists in markdown files:
+//
+//     # groceries
+//
+//     - [ ] milk
+//       - [x] (A) oat milk @due(2024-05-03)
+//         notes are indented below their task
+//
+// only the first checklist in a file is read, and writing puts the list back in its place so
+// the rest of the file is left as it was
+
+use super::super::doolist::{DooItem, DooList};
+use super::super::due;
+use super::todotxt;
+use chrono::{Local, NaiveDateTime};
+
+/// read the first checklist in `text`. nested items become subtasks, lines indented under an
+/// item become its notes and the first heading above the checklist names the list
+pub fn read(text: &str) -> DooList {
+    let lines: Vec<&str> = text.lines().collect();
+    let (start, end) = find_checklist(&lines).unwrap_or((lines.len(), lines.len()));
+
+    let mut doolist = DooList::new();
+    doolist.name = lines[..start]
+        .iter()
+        .find_map(|line| heading(line))
+        .map(|(_, title)| title.to_string());
+
+    let now = Local::now().naive_local();
+    let mut entries: Vec<(usize, DooItem)> = Vec::new();
+    for line in &lines[start..end] {
+        match checklist_item(line) {
+            Some((indent, complete, label)) => {
+                entries.push((indent, read_item(label, complete, now)));
+            }
+            None => {
+                if let Some((indent, item)) = entries.last_mut() {
+                    // notes sit two columns deeper than their task
+                    let note = dedent(line, *indent + 2);
+                    if !item.notes.is_empty() {
+                        item.notes.push('\n');
+                    }
+                    item.notes.push_str(note);
+                }
+            }
+        }
+    }
+    for (_, item) in entries.iter_mut() {
+        item.notes = item.notes.trim().to_string();
+    }
+
+    let mut i = 0;
+    doolist.list = nest(&entries, &mut i, 0);
+    doolist
+}
+
+/// the list as a markdown checklist. when `existing` holds the file being saved over, only its
+/// first checklist is replaced and its first heading renamed, so surrounding prose survives
+pub fn write(doolist: &DooList, existing: Option<&str>) -> String {
+    let mut checklist = String::new();
+    for path in doolist.paths() {
+        if let Some(item) = doolist.get(&path) {
+            write_item(&mut checklist, item, path.len() - 1);
+        }
+    }
+
+    let lines: Vec<&str> = existing.unwrap_or("").lines().collect();
+    let found = find_checklist(&lines);
+    let (start, end) = found.unwrap_or((lines.len(), lines.len()));
+    let mut before: Vec<String> = lines[..start].iter().map(|l| l.to_string()).collect();
+
+    if let Some(name) = &doolist.name {
+        match before.iter().position(|line| heading(line).is_some()) {
+            Some(i) => {
+                let renamed = heading(&before[i])
+                    .filter(|(_, title)| title != name)
+                    .map(|(level, _)| level);
+                if let Some(level) = renamed {
+                    before[i] = format!("{} {}", "#".repeat(level), name);
+                }
+            }
+            None => {
+                before.insert(0, format!("# {}", name));
+                before.insert(1, String::new());
+            }
+        }
+    }
+    // a checklist added to the end of a file needs a blank line to stand apart from it
+    if found.is_none() && before.last().is_some_and(|l| !l.trim().is_empty()) {
+        before.push(String::new());
+    }
+
+    let mut text = String::new();
+    for line in &before {
+        text.push_str(line);
+        text.push('\n');
+    }
+    text.push_str(&checklist);
+    for line in &lines[end..] {
+        text.push_str(line);
+        text.push('\n');
+    }
+    text
+}
+
+fn write_item(out: &mut String, item: &DooItem, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let mut line = format!(
+        "{}- [{}] ",
+        indent,
+        match item.complete {
+            true => "x",
+            false => " ",
+        }
+    );
+    if let Some(p) = item.priority {
+        line.push_str(&format!("({}) ", p));
+    }
+    line.push_str(&item.label);
+    if let Some(d) = item.due {
+        let when = match due::is_all_day(&d) {
+            true => d.format("%Y-%m-%d"),
+            false => d.format("%Y-%m-%d %H:%M"),
+        };
+        line.push_str(&format!(" @due({})", when));
+    }
+    out.push_str(line.trim_end());
+    out.push('\n');
+
+    for note in item.notes.lines() {
+        match note.is_empty() {
+            true => out.push('\n'),
+            false => out.push_str(&format!("{}  {}\n", indent, note)),
+        }
+    }
+}
+
+fn read_item(label: &str, complete: bool, now: NaiveDateTime) -> DooItem {
+    let priority = label
+        .split_whitespace()
+        .next()
+        .and_then(todotxt::parse_priority);
+    let label = match priority {
+        Some(_) => label.split_once(' ').map_or("", |(_, rest)| rest),
+        None => label,
+    };
+    let (label, due) = due::extract(label, now);
+
+    let mut item = DooItem::new(label, complete);
+    item.priority = priority;
+    item.due = due;
+    // markdown keeps no dates of its own
+    item.created = None;
+    item
+}
+
+/// build the tree from items in file order, each with the column it was indented to
+fn nest(entries: &[(usize, DooItem)], i: &mut usize, indent: usize) -> Vec<DooItem> {
+    let mut items = Vec::new();
+    while let Some((own, item)) = entries.get(*i).filter(|(own, _)| *own >= indent) {
+        let mut item = item.clone();
+        *i += 1;
+        item.subtasks = nest(entries, i, own + 1);
+        items.push(item);
+    }
+    items
+}
+
+/// the lines of the first checklist, from its first item to the end of its last item or note.
+/// blank lines may separate items, anything else that is not indented ends it
+fn find_checklist(lines: &[&str]) -> Option<(usize, usize)> {
+    let start = lines
+        .iter()
+        .position(|line| checklist_item(line).is_some())?;
+    let mut end = start + 1;
+    for (i, line) in lines.iter().enumerate().skip(start + 1) {
+        if checklist_item(line).is_some() || (!line.trim().is_empty() && indent_of(line) > 0) {
+            end = i + 1;
+        } else if !line.trim().is_empty() {
+            break;
+        }
+    }
+    Some((start, end))
+}
+
+/// a '- [ ] label' line, as its indent, completion and label
+fn checklist_item(line: &str) -> Option<(usize, bool, &str)> {
+    let trimmed = line.trim_start();
+    let rest = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| trimmed.strip_prefix(bullet))?;
+    let complete = match rest.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let label = &rest[3..];
+    match label.is_empty() || label.starts_with(' ') {
+        true => Some((indent_of(line), complete, label.trim())),
+        false => None,
+    }
+}
+
+/// a '## title' line, as its level and title
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    match (level, line[level..].strip_prefix(' ')) {
+        (1..=6, Some(title)) => Some((level, title.trim())),
+        _ => None,
+    }
+}
+
+/// how many columns `line` is indented, counting a tab as four
+fn indent_of(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| match c {
+            '\t' => 4,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// `line` without up to `columns` columns of leading whitespace
+fn dedent(line: &str, columns: usize) -> &str {
+    let mut width = 0;
+    for (i, c) in line.char_indices() {
+        if width >= columns || !c.is_whitespace() {
+            return &line[i..];
+        }
+        width += match c {
+            '\t' => 4,
+            _ => 1,
+        };
+    }
+    ""
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checklist_is_replaced_in_place() {
+        let text =
+            "# plan\n\nintro\n\n- [ ] docs\n  - [x] api\n    see #12\n- [ ] ship\n\n## after\n";
+        let mut doolist = read(text);
+        assert_eq!(doolist.name.as_deref(), Some("plan"));
+        assert_eq!(doolist.list.len(), 2);
+        assert_eq!(doolist.list[0].subtasks[0].label, "api");
+        assert_eq!(doolist.list[0].subtasks[0].notes, "see #12");
+        assert_eq!(write(&doolist, Some(text)), text);
+
+        doolist.change_name(String::from("release"));
+        doolist.select_path(&[1]);
+        doolist.mark_selection().unwrap();
+        assert_eq!(
+            write(&doolist, Some(text)),
+            text.replace("# plan", "# release")
+                .replace("[ ] ship", "[x] ship")
+        );
+    }
+
+    #[test]
+    fn priority_and_due_round_trip() {
+        let text = "- [ ] (A) call mom @due(2024-05-03)\n- [X] ship @due(2024-05-03 17:30)\n";
+        let doolist = read(text);
+        assert_eq!(doolist.name, None);
+        assert_eq!(doolist.list[0].priority, Some('A'));
+        assert_eq!(doolist.list[0].label, "call mom");
+        assert!(due::is_all_day(&doolist.list[0].due.unwrap()));
+        assert!(doolist.list[1].complete);
+        assert_eq!(
+            doolist.list[1].due.unwrap().to_string(),
+            "2024-05-03 17:30:00"
+        );
+        assert_eq!(write(&doolist, None), text.replace("[X]", "[x]"));
+    }
+
+    #[test]
+    fn only_the_first_checklist_is_read() {
+        let text = "* [ ] one\n\n+ [ ] two\n- plain bullet\n- [ ] three\n- [-] odd\n";
+        let doolist = read(text);
+        let labels: Vec<&str> = doolist.list.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, vec!["one", "two"]);
+
+        let mut edited = doolist;
+        edited.list.truncate(1);
+        assert_eq!(
+            write(&edited, Some(text)),
+            "- [ ] one\n- plain bullet\n- [ ] three\n- [-] odd\n"
+        );
+    }
+
+    #[test]
+    fn a_file_without_a_checklist_gets_one_at_the_end() {
+        let mut doolist = read("");
+        assert!(doolist.list.is_empty());
+        doolist.list.push(read("- [ ] milk").list.remove(0));
+
+        assert_eq!(
+            write(&doolist, Some("some prose")),
+            "some prose\n\n- [ ] milk\n"
+        );
+        doolist.name = Some(String::from("groceries"));
+        assert_eq!(write(&doolist, None), "# groceries\n\n- [ ] milk\n");
+    }
+
+    #[test]
+    fn notes_keep_blank_lines_and_tabs_count_as_four() {
+        let text = "- [ ] plan\n  first\n\n  second\n\t- [ ] deep\n";
+        let doolist = read(text);
+        assert_eq!(doolist.list[0].notes, "first\n\nsecond");
+        assert_eq!(doolist.list[0].subtasks[0].label, "deep");
+        assert_eq!(
+            write(&doolist, None),
+            "- [ ] plan\n  first\n\n  second\n  - [ ] deep\n"
+        );
+    }
+}
@@ -0,0 +1,34 @@
+pub mod markdown;
+pub mod todotxt;
+
+use std::path::Path;
+
+/// the file formats a list can be imported from and exported to
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    /// doo's own json
+    Doo,
+    TodoTxt,
+    /// '- [ ]' checklists in a markdown file
+    Markdown,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name.to_lowercase().as_str() {
+            "doo" | "json" => Some(Format::Doo),
+            "todo.txt" | "todotxt" | "txt" => Some(Format::TodoTxt),
+            "markdown" | "md" => Some(Format::Markdown),
+            _ => None,
+        }
+    }
+
+    /// guess the format from a file name, falling back to doo's own
+    pub fn from_path(path: &str) -> Format {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("txt") => Format::TodoTxt,
+            Some("md") | Some("markdown") => Format::Markdown,
+            _ => Format::Doo,
+        }
+    }
+}
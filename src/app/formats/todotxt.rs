@@ -0,0 +1,192 @@
+// the todo.txt format, one task per line:
+//
+//     (A) 2024-05-01 call mom +family @phone due:2024-05-03
+//     x 2024-05-02 2024-05-01 call mom +family @phone due:2024-05-03 pri:A
+//
+// a completed task has no '(A)' marker, so its priority is kept as a 'pri:A' tag instead.
+// todo.txt has no nesting, so subtasks are written out as their own lines right after their
+// parent, and notes are left out
+
+use super::super::doolist::{DooItem, DooList};
+use super::super::due;
+use chrono::NaiveDate;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// every item in `doolist` as a todo.txt line, depth first
+pub fn write(doolist: &DooList) -> String {
+    doolist
+        .paths()
+        .iter()
+        .filter_map(|path| doolist.get(path))
+        .map(|item| format!("{}\n", write_item(item)))
+        .collect()
+}
+
+/// every task in a todo.txt file, skipping blank lines
+pub fn read(text: &str) -> Vec<DooItem> {
+    text.lines().filter_map(read_item).collect()
+}
+
+fn write_item(item: &DooItem) -> String {
+    let mut parts: Vec<String> = Vec::new();
+
+    match item.complete {
+        true => {
+            parts.push(String::from("x"));
+            // a creation date is only allowed after a completion date
+            if let Some(completed) = item.completed {
+                parts.push(completed.format(DATE_FORMAT).to_string());
+                parts.extend(item.created.map(|d| d.format(DATE_FORMAT).to_string()));
+            }
+        }
+        false => {
+            parts.extend(item.priority.map(|p| format!("({})", p)));
+            parts.extend(item.created.map(|d| d.format(DATE_FORMAT).to_string()));
+        }
+    }
+
+    // a label can not span lines in todo.txt
+    parts.push(
+        item.label
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" "),
+    );
+
+    parts.extend(
+        item.due
+            .map(|d| format!("due:{}", d.date().format(DATE_FORMAT))),
+    );
+    // completed tasks lose their priority in todo.txt, so keep it the way most tools do
+    if item.complete {
+        parts.extend(item.priority.map(|p| format!("pri:{}", p)));
+    }
+
+    parts.retain(|p| !p.is_empty());
+    parts.join(" ")
+}
+
+fn read_item(line: &str) -> Option<DooItem> {
+    let mut words = line.split_whitespace().peekable();
+    words.peek()?;
+
+    let complete = words.next_if_eq(&"x").is_some();
+    let mut priority = match complete {
+        true => None,
+        false => words
+            .next_if(|w| parse_priority(w).is_some())
+            .and_then(parse_priority),
+    };
+    let first = words
+        .next_if(|w| parse_date(w).is_some())
+        .and_then(parse_date);
+    let second = words
+        .next_if(|w| parse_date(w).is_some())
+        .and_then(parse_date);
+    let (completed, created) = match complete {
+        true => (first, second),
+        false => (None, first),
+    };
+
+    let mut due = None;
+    let mut label: Vec<&str> = Vec::new();
+    for word in words {
+        match word.split_once(':') {
+            Some(("due", date)) if parse_date(date).is_some() => {
+                due = parse_date(date).map(|d| d.and_time(due::end_of_day()));
+            }
+            Some(("pri", p)) if p.len() == 1 && p.chars().all(|c| c.is_ascii_uppercase()) => {
+                priority = p.chars().next();
+            }
+            _ => label.push(word),
+        }
+    }
+
+    let mut item = DooItem::new(label.join(" "), complete);
+    item.priority = priority;
+    item.created = created;
+    item.completed = completed;
+    item.due = due;
+    Some(item)
+}
+
+/// a '(A)' priority marker
+pub fn parse_priority(word: &str) -> Option<char> {
+    let mut chars = word.strip_prefix('(')?.strip_suffix(')')?.chars();
+    match (chars.next(), chars.next()) {
+        (Some(p), None) if p.is_ascii_uppercase() => Some(p),
+        _ => None,
+    }
+}
+
+fn parse_date(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, DATE_FORMAT).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(items: Vec<DooItem>) -> DooList {
+        let mut doolist = DooList::new();
+        doolist.list = items;
+        doolist
+    }
+
+    #[test]
+    fn round_trips_dates_priority_and_projects() {
+        let text = "(A) 2024-05-01 call mom +family @phone due:2024-05-03\n\n\
+                    x 2024-05-02 2024-04-30 file taxes pri:B\n";
+        let items = read(text);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].priority, Some('A'));
+        assert_eq!(items[0].label, "call mom +family @phone");
+        assert!(items[0].tags.contains("+family") && items[0].tags.contains("@phone"));
+        assert_eq!(items[0].due.unwrap().date().to_string(), "2024-05-03");
+        assert!(items[1].complete);
+        assert_eq!(items[1].completed.unwrap().to_string(), "2024-05-02");
+        assert_eq!(items[1].created.unwrap().to_string(), "2024-04-30");
+        assert_eq!(items[1].priority, Some('B'));
+
+        assert_eq!(write(&list(items)), text.replace("\n\n", "\n"));
+    }
+
+    #[test]
+    fn completed_tasks_keep_their_priority_as_a_tag() {
+        let mut item = DooItem::new(String::from("call mom"), false);
+        item.priority = Some('A');
+        item.created = NaiveDate::from_ymd_opt(2024, 5, 1);
+        item.set_complete(true);
+        item.completed = NaiveDate::from_ymd_opt(2024, 5, 2);
+
+        let text = write(&list(vec![item]));
+        assert_eq!(text, "x 2024-05-02 2024-05-01 call mom pri:A\n");
+        assert_eq!(read(&text)[0].priority, Some('A'));
+        // a marker after the 'x' is not a priority, as todo.txt has none there
+        assert_eq!(read("x (A) call mom")[0].priority, None);
+    }
+
+    #[test]
+    fn subtasks_become_lines_and_labels_stay_on_one() {
+        let mut parent = DooItem::new(String::from("plan"), false);
+        parent.created = None;
+        let mut child = DooItem::new(String::from("write\nit   down"), false);
+        child.created = None;
+        child.notes = String::from("left out");
+        parent.subtasks.push(child);
+
+        assert_eq!(write(&list(vec![parent])), "plan\nwrite it down\n");
+    }
+
+    #[test]
+    fn odd_words_stay_in_the_label() {
+        let item = &read("(a) 2024-13-01 due:soon pri:AB x")[0];
+        assert_eq!(item.priority, None);
+        assert_eq!(item.created, None);
+        assert_eq!(item.due, None);
+        assert_eq!(item.label, "(a) 2024-13-01 due:soon pri:AB x");
+        assert!(read("   \n\n").is_empty());
+        assert_eq!(parse_priority("(AB)"), None);
+    }
+}
@@ -0,0 +1,189 @@
+use super::doolist::{DooItem, ItemPath};
+use std::collections::VecDeque;
+
+/// a single change to a list, holding enough to both revert and replay it
+#[derive(Clone)]
+pub enum Edit {
+    /// `item` was inserted at `path`
+    Insert {
+        path: ItemPath,
+        item: DooItem,
+        /// completion of each ancestor of `path` before the edit, outermost first
+        ancestors: Vec<bool>,
+    },
+    /// `item` was removed from `path`
+    Delete {
+        path: ItemPath,
+        item: DooItem,
+        ancestors: Vec<bool>,
+    },
+    /// the item at `path` changed in place, e.g. it was marked or relabeled
+    Update {
+        path: ItemPath,
+        before: DooItem,
+        after: DooItem,
+        ancestors: Vec<bool>,
+    },
+    /// the list itself was renamed
+    Rename {
+        before: Option<String>,
+        after: Option<String>,
+    },
+    /// the whole list was rewritten at once, e.g. by sorting it
+    Replace {
+        before: Vec<DooItem>,
+        after: Vec<DooItem>,
+        /// the selected item before and after the edit
+        selected_before: Option<ItemPath>,
+        selected_after: Option<ItemPath>,
+    },
+    /// a child of `parent` moved from index `from` to index `to`
+    Move {
+        parent: ItemPath,
+        from: usize,
+        to: usize,
+    },
+}
+
+/// undo and redo stacks for a list. the undo stack is capped at `depth` edits, dropping the
+/// oldest ones first
+pub struct History {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    depth: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new(100)
+    }
+}
+
+impl History {
+    pub fn new(depth: usize) -> History {
+        History {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            depth,
+        }
+    }
+
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        while self.undo.len() > depth {
+            self.undo.pop_front();
+        }
+    }
+
+    /// record a fresh edit, which invalidates anything that could be redone
+    pub fn record(&mut self, edit: Edit) {
+        self.redo.clear();
+        self.push_undo(edit);
+    }
+
+    fn push_undo(&mut self, edit: Edit) {
+        if self.depth == 0 {
+            return;
+        }
+        if self.undo.len() == self.depth {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(edit);
+    }
+
+    /// the most recent edit, as long as nothing has been undone since
+    pub fn last(&self) -> Option<&Edit> {
+        match self.redo.is_empty() {
+            true => self.undo.back(),
+            false => None,
+        }
+    }
+
+    /// the most recent edit, as long as nothing has been undone since
+    pub fn last_mut(&mut self) -> Option<&mut Edit> {
+        match self.redo.is_empty() {
+            true => self.undo.back_mut(),
+            false => None,
+        }
+    }
+
+    /// take the most recent edit to revert, moving it onto the redo stack
+    pub fn undo(&mut self) -> Option<Edit> {
+        let edit = self.undo.pop_back()?;
+        self.redo.push(edit.clone());
+        Some(edit)
+    }
+
+    /// take the most recently undone edit to replay, moving it back onto the undo stack
+    pub fn redo(&mut self) -> Option<Edit> {
+        let edit = self.redo.pop()?;
+        self.push_undo(edit.clone());
+        Some(edit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rename(after: &str) -> Edit {
+        Edit::Rename {
+            before: None,
+            after: Some(String::from(after)),
+        }
+    }
+
+    fn name(edit: Option<&Edit>) -> Option<&str> {
+        match edit {
+            Some(Edit::Rename { after, .. }) => after.as_deref(),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn depth_zero_keeps_nothing_to_undo() {
+        let mut history = History::new(0);
+        history.record(rename("a"));
+        assert!(history.last().is_none());
+        assert!(history.undo().is_none());
+    }
+
+    #[test]
+    fn lowering_the_depth_drops_the_oldest_edits() {
+        let mut history = History::new(3);
+        for after in ["a", "b", "c"] {
+            history.record(rename(after));
+        }
+        history.set_depth(1);
+        assert_eq!(name(history.undo().as_ref()), Some("c"));
+        assert!(history.undo().is_none());
+    }
+
+    #[test]
+    fn recording_clears_what_could_be_redone() {
+        let mut history = History::default();
+        history.record(rename("a"));
+        history.record(rename("b"));
+        history.undo();
+        history.record(rename("c"));
+        assert!(history.redo().is_none());
+        assert_eq!(name(history.undo().as_ref()), Some("c"));
+        assert_eq!(name(history.undo().as_ref()), Some("a"));
+    }
+
+    #[test]
+    fn last_edit_is_hidden_once_something_is_undone() {
+        let mut history = History::default();
+        history.record(rename("a"));
+        history.record(rename("b"));
+        assert_eq!(name(history.last()), Some("b"));
+
+        history.undo();
+        assert!(history.last().is_none());
+        assert!(history.last_mut().is_none());
+
+        assert_eq!(name(history.redo().as_ref()), Some("b"));
+        assert_eq!(name(history.last()), Some("b"));
+        assert!(history.last_mut().is_some());
+    }
+}
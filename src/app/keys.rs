@@ -0,0 +1,476 @@
+use crate::error::{DooError, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+
+/// everything a key can be bound to, named as in the 'keys' and 'visual_keys' config sections
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    Quit,
+    Add,
+    AddSubtask,
+    Down,
+    Up,
+    RaisePriority,
+    LowerPriority,
+    MoveDown,
+    MoveUp,
+    MoveTop,
+    MoveBottom,
+    Fold,
+    Unfold,
+    Mark,
+    Delete,
+    Undo,
+    Redo,
+    Edit,
+    Notes,
+    Command,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    ClearSearch,
+    /// start selecting a range of tasks, or a new range while already selecting
+    Visual,
+    /// add the task under the cursor to the selection, or take it out
+    Toggle,
+    Yank,
+    /// paste the tasks in a register below the selected one
+    Paste,
+    PasteBefore,
+    /// the next key names the register to yank, delete or paste with
+    Register,
+    /// stop selecting
+    Leave,
+}
+
+const ACTIONS: &[(&str, Action)] = &[
+    ("quit", Action::Quit),
+    ("add", Action::Add),
+    ("add_subtask", Action::AddSubtask),
+    ("down", Action::Down),
+    ("up", Action::Up),
+    ("raise_priority", Action::RaisePriority),
+    ("lower_priority", Action::LowerPriority),
+    ("move_down", Action::MoveDown),
+    ("move_up", Action::MoveUp),
+    ("move_top", Action::MoveTop),
+    ("move_bottom", Action::MoveBottom),
+    ("fold", Action::Fold),
+    ("unfold", Action::Unfold),
+    ("mark", Action::Mark),
+    ("delete", Action::Delete),
+    ("undo", Action::Undo),
+    ("redo", Action::Redo),
+    ("edit", Action::Edit),
+    ("notes", Action::Notes),
+    ("command", Action::Command),
+    ("search", Action::Search),
+    ("next_match", Action::NextMatch),
+    ("previous_match", Action::PreviousMatch),
+    ("clear_search", Action::ClearSearch),
+    ("visual", Action::Visual),
+    ("toggle", Action::Toggle),
+    ("yank", Action::Yank),
+    ("paste", Action::Paste),
+    ("paste_before", Action::PasteBefore),
+    ("register", Action::Register),
+    ("leave", Action::Leave),
+];
+
+/// the bindings doo starts with, which the 'keys' config section adds to or overrides
+const DEFAULT_KEYS: &[(&str, &str)] = &[
+    ("q", "quit"),
+    ("a", "add"),
+    ("s", "add_subtask"),
+    ("j", "down"),
+    ("down", "down"),
+    ("k", "up"),
+    ("up", "up"),
+    ("+", "raise_priority"),
+    ("-", "lower_priority"),
+    ("J", "move_down"),
+    ("K", "move_up"),
+    ("gg", "move_top"),
+    ("G", "move_bottom"),
+    ("h", "fold"),
+    ("l", "unfold"),
+    ("x", "mark"),
+    ("d", "delete"),
+    ("u", "undo"),
+    ("ctrl-r", "redo"),
+    ("i", "edit"),
+    ("enter", "notes"),
+    (":", "command"),
+    ("/", "search"),
+    ("n", "next_match"),
+    ("N", "previous_match"),
+    ("esc", "clear_search"),
+    ("V", "visual"),
+    ("yy", "yank"),
+    ("p", "paste"),
+    ("P", "paste_before"),
+    ("\"", "register"),
+];
+
+/// the bindings of visual mode, which the 'visual_keys' config section adds to or overrides
+const DEFAULT_VISUAL_KEYS: &[(&str, &str)] = &[
+    ("j", "down"),
+    ("down", "down"),
+    ("k", "up"),
+    ("up", "up"),
+    ("J", "move_down"),
+    ("K", "move_up"),
+    ("gg", "move_top"),
+    ("G", "move_bottom"),
+    ("+", "raise_priority"),
+    ("-", "lower_priority"),
+    ("x", "mark"),
+    ("d", "delete"),
+    ("y", "yank"),
+    ("\"", "register"),
+    ("space", "toggle"),
+    ("V", "visual"),
+    (":", "command"),
+    ("esc", "leave"),
+    ("q", "leave"),
+];
+
+/// binding a key to this removes whatever it was bound to
+const UNBOUND: &str = "none";
+
+impl Action {
+    pub fn parse(name: &str) -> Option<Action> {
+        ACTIONS
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, action)| *action)
+    }
+}
+
+/// one key press, with the modifiers that matter
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Chord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Chord {
+    /// the key as pressed. shift is part of the char for letters and symbols, so it only
+    /// counts for the other keys
+    pub fn from_event(key: &KeyEvent) -> Chord {
+        let mut modifiers = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        if !matches!(key.code, KeyCode::Char(_)) {
+            modifiers |= key.modifiers & KeyModifiers::SHIFT;
+        }
+        Chord {
+            code: key.code,
+            modifiers,
+        }
+    }
+
+    /// a key as written in the config: a char such as 'x' or 'G', a name such as 'enter',
+    /// 'space' or 'f1', each optionally after 'ctrl-', 'alt-' or 'shift-'
+    fn parse(text: &str) -> Option<Chord> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+        loop {
+            let lower = rest.to_lowercase();
+            let (modifier, len) = if lower.starts_with("ctrl-") {
+                (KeyModifiers::CONTROL, 5)
+            } else if lower.starts_with("alt-") {
+                (KeyModifiers::ALT, 4)
+            } else if lower.starts_with("shift-") {
+                (KeyModifiers::SHIFT, 6)
+            } else {
+                break;
+            };
+            // a lone '-' after a modifier is the key itself, as in 'ctrl--'
+            if rest.len() == len {
+                break;
+            }
+            modifiers |= modifier;
+            rest = &rest[len..];
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => match modifiers.contains(KeyModifiers::SHIFT) {
+                true => {
+                    modifiers.remove(KeyModifiers::SHIFT);
+                    KeyCode::Char(c.to_ascii_uppercase())
+                }
+                false => KeyCode::Char(c),
+            },
+            _ => match rest.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                name => KeyCode::F(name.strip_prefix('f')?.parse().ok()?),
+            },
+        };
+        // ctrl and alt come with the lowercase letter
+        let code = match code {
+            KeyCode::Char(c) if !modifiers.is_empty() => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        Some(Chord { code, modifiers })
+    }
+}
+
+/// a key sequence as written in the config. keys are separated by spaces, as in
+/// 'ctrl-x ctrl-s', except that a run of plain chars such as 'gg' or 'dd' is one key each
+fn parse_sequence(text: &str) -> Option<Vec<Chord>> {
+    match text {
+        " " => Chord::parse("space").map(|chord| vec![chord]),
+        text if text.trim().contains(' ') => text.split_whitespace().map(Chord::parse).collect(),
+        text => Chord::parse(text)
+            .map(|chord| vec![chord])
+            .or_else(|| text.chars().map(|c| Chord::parse(&c.to_string())).collect()),
+    }
+}
+
+/// what the keys pressed so far amount to
+pub enum Lookup {
+    Action(Action),
+    /// the start of a longer sequence, wait for the next key
+    Pending,
+    None,
+}
+
+struct Binding {
+    /// the keys as written, for error messages
+    written: String,
+    sequence: Vec<Chord>,
+    action: Action,
+}
+
+/// which key sequences run which actions, in select or in visual mode
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(&BTreeMap::new()).expect("the default keys are valid")
+    }
+}
+
+impl Keymap {
+    /// the default bindings with those from the 'keys' config section laid over them. every
+    /// key that can not be read or names no action is reported in one error
+    pub fn new(keys: &BTreeMap<String, String>) -> Result<Keymap> {
+        Keymap::build(DEFAULT_KEYS, keys)
+    }
+
+    /// the bindings of visual mode, with those from the 'visual_keys' config section laid
+    /// over them
+    pub fn visual(keys: &BTreeMap<String, String>) -> Result<Keymap> {
+        Keymap::build(DEFAULT_VISUAL_KEYS, keys).map_err(|e| match e {
+            DooError::Config(problems) => DooError::Config(format!("visual_keys: {}", problems)),
+            e => e,
+        })
+    }
+
+    fn build(defaults: &[(&str, &str)], keys: &BTreeMap<String, String>) -> Result<Keymap> {
+        let mut bindings: Vec<Binding> = Vec::new();
+        let mut problems = Vec::new();
+
+        let defaults = defaults.iter().map(|(k, a)| (*k, *a));
+        let configured = keys.iter().map(|(k, a)| (k.as_str(), a.as_str()));
+        for (key, name) in defaults.chain(configured) {
+            let sequence = match parse_sequence(key) {
+                Some(sequence) if !sequence.is_empty() => sequence,
+                _ => {
+                    problems.push(format!("not a key: '{}'", key));
+                    continue;
+                }
+            };
+            bindings.retain(|b| b.sequence != sequence);
+            if name == UNBOUND {
+                continue;
+            }
+            match Action::parse(name) {
+                Some(action) => bindings.push(Binding {
+                    written: key.to_string(),
+                    sequence,
+                    action,
+                }),
+                None => problems.push(format!("unknown action '{}' for '{}'", name, key)),
+            }
+        }
+
+        // a key that runs an action can not also start a longer sequence
+        for short in &bindings {
+            for long in &bindings {
+                if long.sequence.len() > short.sequence.len()
+                    && long.sequence.starts_with(&short.sequence)
+                {
+                    problems.push(format!(
+                        "'{}' starts '{}', bind one of them to '{}'",
+                        short.written, long.written, UNBOUND
+                    ));
+                }
+            }
+        }
+
+        match problems.is_empty() {
+            true => Ok(Keymap { bindings }),
+            false => {
+                problems.dedup();
+                Err(DooError::Config(problems.join(", ")))
+            }
+        }
+    }
+
+    /// the action for the keys pressed so far
+    pub fn lookup(&self, pressed: &[Chord]) -> Lookup {
+        let mut pending = false;
+        for binding in &self.bindings {
+            if binding.sequence == pressed {
+                return Lookup::Action(binding.action);
+            }
+            pending |= binding.sequence.starts_with(pressed);
+        }
+        match pending {
+            true => Lookup::Pending,
+            false => Lookup::None,
+        }
+    }
+
+    /// the action bound to a single key, as used to move around the picker screens
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        match self.lookup(&[Chord::from_event(key)]) {
+            Lookup::Action(action) => Some(action),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(keys: &[(&str, &str)]) -> BTreeMap<String, String> {
+        keys.iter()
+            .map(|(k, a)| (k.to_string(), a.to_string()))
+            .collect()
+    }
+
+    fn press(c: char, modifiers: KeyModifiers) -> Chord {
+        Chord::from_event(&KeyEvent::new(KeyCode::Char(c), modifiers))
+    }
+
+    fn action(keys: &Keymap, pressed: &[Chord]) -> Option<Action> {
+        match keys.lookup(pressed) {
+            Lookup::Action(action) => Some(action),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn config_is_laid_over_the_defaults() {
+        let keys = Keymap::new(&config(&[
+            ("n", "down"),
+            ("e", "up"),
+            ("ctrl-n", "next_match"),
+            ("d", "none"),
+            ("dd", "delete"),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            action(&keys, &[press('n', KeyModifiers::NONE)]),
+            Some(Action::Down)
+        );
+        assert_eq!(
+            action(&keys, &[press('n', KeyModifiers::CONTROL)]),
+            Some(Action::NextMatch)
+        );
+        // untouched defaults stay, and shift is part of the letter
+        assert_eq!(
+            action(&keys, &[press('G', KeyModifiers::SHIFT)]),
+            Some(Action::MoveBottom)
+        );
+        let d = press('d', KeyModifiers::NONE);
+        assert!(matches!(keys.lookup(&[d]), Lookup::Pending));
+        assert_eq!(action(&keys, &[d, d]), Some(Action::Delete));
+        assert!(matches!(
+            keys.lookup(&[press('d', KeyModifiers::ALT)]),
+            Lookup::None
+        ));
+    }
+
+    #[test]
+    fn every_bad_key_is_reported_at_once() {
+        let error = Keymap::new(&config(&[
+            ("dd", "delete"),
+            ("z", "zap"),
+            ("ctrl-x bogus", "up"),
+        ]))
+        .err()
+        .unwrap()
+        .to_string();
+        assert!(error.starts_with("config: "));
+        assert!(error.contains("unknown action 'zap' for 'z'"));
+        assert!(error.contains("'d' starts 'dd'"));
+        assert!(error.contains("not a key: 'ctrl-x bogus'"));
+
+        let error = Keymap::visual(&config(&[("q", "fly")]))
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(error.starts_with("config: visual_keys: unknown action 'fly'"));
+    }
+
+    #[test]
+    fn keys_are_written_the_way_they_read() {
+        let chord = |text| Chord::parse(text).unwrap();
+        assert_eq!(chord("shift-g"), press('G', KeyModifiers::NONE));
+        assert_eq!(chord("Ctrl-S"), press('s', KeyModifiers::CONTROL));
+        assert_eq!(chord("ctrl--"), press('-', KeyModifiers::CONTROL));
+        assert_eq!(chord("space"), press(' ', KeyModifiers::NONE));
+        assert_eq!(chord("f5").code, KeyCode::F(5));
+        assert_eq!(
+            chord("shift-tab"),
+            Chord {
+                code: KeyCode::Tab,
+                modifiers: KeyModifiers::SHIFT
+            }
+        );
+        assert_eq!(Chord::parse("f"), Some(press('f', KeyModifiers::NONE)));
+        assert_eq!(Chord::parse("fx"), None);
+
+        assert_eq!(parse_sequence("ctrl-x ctrl-s").unwrap().len(), 2);
+        assert_eq!(parse_sequence("gg").unwrap().len(), 2);
+        assert_eq!(parse_sequence("enter").unwrap().len(), 1);
+        assert_eq!(parse_sequence(" ").unwrap(), vec![chord("space")]);
+    }
+
+    #[test]
+    fn visual_mode_has_its_own_keys() {
+        let select = Keymap::default();
+        let visual = Keymap::visual(&BTreeMap::new()).unwrap();
+        let y = press('y', KeyModifiers::NONE);
+
+        assert!(matches!(select.lookup(&[y]), Lookup::Pending));
+        assert_eq!(action(&select, &[y, y]), Some(Action::Yank));
+        assert_eq!(action(&visual, &[y]), Some(Action::Yank));
+        assert_eq!(
+            visual.action(&KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)),
+            Some(Action::Leave)
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_width::UnicodeWidthStr;
+
+/// a single line of text being typed, as a task name, command or search, with a cursor that
+/// can be moved around it
+pub struct LineEditor {
+    text: String,
+    /// cursor position, counted in chars
+    cursor: usize,
+    /// whether esc leaves for vim style normal mode instead of dropping the line
+    vim: bool,
+    /// in normal mode keys move the cursor instead of typing
+    pub normal: bool,
+}
+
+impl LineEditor {
+    pub fn new(vim: bool) -> LineEditor {
+        LineEditor {
+            text: String::new(),
+            cursor: 0,
+            vim,
+            normal: false,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// start over with `text`, the cursor at its end
+    pub fn set(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.cursor = self.len();
+        self.normal = false;
+    }
+
+    pub fn clear(&mut self) {
+        self.set("");
+    }
+
+    /// how many columns the text before the cursor takes up on screen
+    pub fn cursor_width(&self) -> usize {
+        self.text[..self.byte_index(self.cursor)].width()
+    }
+
+    /// apply an editing key. returns false when the key means nothing to the editor, which
+    /// for esc means the line should be dropped
+    pub fn handle(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Esc if self.vim && !self.normal => {
+                self.normal = true;
+                // like vim, the cursor sits on the last char typed
+                self.cursor = self.cursor.saturating_sub(1);
+            }
+            KeyCode::Char(c) if self.normal && !ctrl && !alt => return self.normal_key(c),
+            KeyCode::Char('w') if ctrl => self.delete_word_back(),
+            KeyCode::Char('u') if ctrl => self.delete_to_start(),
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.len(),
+            KeyCode::Char('b') if alt => self.cursor = self.word_start(),
+            KeyCode::Char('f') if alt => self.cursor = self.word_end(),
+            KeyCode::Char(c) if !ctrl => self.insert(c),
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.len(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// the keys of vim's normal mode that make sense on one line
+    fn normal_key(&mut self, c: char) -> bool {
+        let last = self.len().saturating_sub(1);
+        match c {
+            'h' => self.cursor = self.cursor.saturating_sub(1),
+            'l' => self.cursor = (self.cursor + 1).min(last),
+            '0' | '^' => self.cursor = 0,
+            '$' => self.cursor = last,
+            'b' => self.cursor = self.word_start(),
+            'w' => self.cursor = self.next_word().min(last),
+            'e' => {
+                self.cursor = (self.cursor + 1).min(self.len());
+                self.cursor = self.word_end().saturating_sub(1);
+            }
+            'x' => {
+                self.delete();
+                self.cursor = self.cursor.min(self.len().saturating_sub(1));
+            }
+            'D' => {
+                let i = self.byte_index(self.cursor);
+                self.text.truncate(i);
+                self.cursor = self.cursor.saturating_sub(1);
+            }
+            'i' => self.normal = false,
+            'a' => {
+                self.cursor = (self.cursor + 1).min(self.len());
+                self.normal = false;
+            }
+            'I' => {
+                self.cursor = 0;
+                self.normal = false;
+            }
+            'A' => {
+                self.cursor = self.len();
+                self.normal = false;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+
+    fn chars(&self) -> Vec<char> {
+        self.text.chars().collect()
+    }
+
+    fn insert(&mut self, c: char) {
+        let i = self.byte_index(self.cursor);
+        self.text.insert(i, c);
+        self.cursor += 1;
+    }
+
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.delete();
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.len() {
+            let i = self.byte_index(self.cursor);
+            self.text.remove(i);
+        }
+    }
+
+    /// remove from the cursor back to the start of the word before it, as in a shell
+    fn delete_word_back(&mut self) {
+        let chars = self.chars();
+        let mut start = self.cursor;
+        while start > 0 && chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        self.remove(start, self.cursor);
+    }
+
+    fn delete_to_start(&mut self) {
+        self.remove(0, self.cursor);
+    }
+
+    fn remove(&mut self, from: usize, to: usize) {
+        let range = self.byte_index(from)..self.byte_index(to);
+        self.text.replace_range(range, "");
+        self.cursor = from;
+    }
+
+    /// the start of the word at or before the cursor
+    fn word_start(&self) -> usize {
+        let chars = self.chars();
+        let mut i = self.cursor;
+        while i > 0 && !chars[i - 1].is_alphanumeric() {
+            i -= 1;
+        }
+        while i > 0 && chars[i - 1].is_alphanumeric() {
+            i -= 1;
+        }
+        i
+    }
+
+    /// just past the end of the word at or after the cursor
+    fn word_end(&self) -> usize {
+        let chars = self.chars();
+        let mut i = self.cursor;
+        while i < chars.len() && !chars[i].is_alphanumeric() {
+            i += 1;
+        }
+        while i < chars.len() && chars[i].is_alphanumeric() {
+            i += 1;
+        }
+        i
+    }
+
+    /// the start of the word after the cursor
+    fn next_word(&self) -> usize {
+        let chars = self.chars();
+        let mut i = self.cursor;
+        while i < chars.len() && chars[i].is_alphanumeric() {
+            i += 1;
+        }
+        while i < chars.len() && !chars[i].is_alphanumeric() {
+            i += 1;
+        }
+        i
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn alt(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::ALT)
+    }
+
+    fn ctrl(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    /// type `keys` as plain chars, one key each
+    fn keys(input: &mut LineEditor, keys: &str) {
+        for c in keys.chars() {
+            input.handle(key(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn moves_by_words_and_keeps_wide_chars_whole() {
+        let mut input = LineEditor::new(false);
+        input.set("buy 牛奶 today");
+        input.handle(alt('b'));
+        input.handle(alt('b'));
+        assert_eq!(input.cursor_width(), "buy ".len());
+        input.handle(key(KeyCode::Right));
+        // 牛 takes two columns
+        assert_eq!(input.cursor_width(), 6);
+        input.handle(key(KeyCode::Backspace));
+        assert_eq!(input.text(), "buy 奶 today");
+        input.handle(alt('f'));
+        assert_eq!(input.cursor_width(), "buy ".len() + 2);
+    }
+
+    #[test]
+    fn shell_keys_delete_words_and_lines() {
+        let mut input = LineEditor::new(false);
+        input.set("buy milk  today");
+        input.handle(ctrl('w'));
+        assert_eq!(input.text(), "buy milk  ");
+        input.handle(ctrl('w'));
+        assert_eq!(input.text(), "buy ");
+
+        input.handle(ctrl('a'));
+        keys(&mut input, "!");
+        input.handle(ctrl('u'));
+        assert_eq!(input.text(), "buy ");
+        input.handle(ctrl('e'));
+        input.handle(key(KeyCode::Delete));
+        assert_eq!(input.text(), "buy ");
+
+        // other control keys are not typed, and esc drops the line outside vim mode
+        assert!(!input.handle(ctrl('z')));
+        assert_eq!(input.text(), "buy ");
+        assert!(!input.handle(key(KeyCode::Esc)));
+    }
+
+    #[test]
+    fn an_empty_line_takes_every_key() {
+        let mut input = LineEditor::new(true);
+        for event in [
+            key(KeyCode::Backspace),
+            key(KeyCode::Delete),
+            key(KeyCode::Left),
+            ctrl('w'),
+            alt('b'),
+            key(KeyCode::Esc),
+        ] {
+            input.handle(event);
+        }
+        keys(&mut input, "hlwebxD$");
+        assert_eq!((input.text(), input.cursor), ("", 0));
+    }
+
+    #[test]
+    fn vim_normal_mode_edits_the_line() {
+        let mut input = LineEditor::new(true);
+        input.set("fix typp");
+        assert!(input.handle(key(KeyCode::Esc)));
+        assert!(input.normal);
+        keys(&mut input, "xao");
+        assert_eq!(input.text(), "fix typo");
+        assert!(!input.normal);
+
+        input.handle(key(KeyCode::Esc));
+        keys(&mut input, "0w");
+        assert_eq!(input.cursor, 4);
+        keys(&mut input, "D");
+        assert_eq!(input.text(), "fix ");
+        keys(&mut input, "Anow");
+        assert_eq!(input.text(), "fix now");
+
+        input.handle(key(KeyCode::Esc));
+        keys(&mut input, "be");
+        assert_eq!(input.cursor, 6);
+        keys(&mut input, "Ido ");
+        assert_eq!(input.text(), "do fix now");
+        // keys normal mode does not know are left to the caller
+        input.handle(key(KeyCode::Esc));
+        assert!(!input.handle(key(KeyCode::Char('z'))));
+    }
+}
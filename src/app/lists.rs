@@ -3,14 +3,8 @@ pub trait Navigate {
     fn next(&mut self) -> ();
 }
 
-pub trait Saveable {
-    fn save() -> ();
-}
-
-pub trait Loadable {
-    fn load() -> ();
-}
-
+// placeholder, not wired up yet
+#[allow(dead_code)]
 pub trait Selectable {
     fn select() -> ();
 }
@@ -0,0 +1,206 @@
+use super::doolist::DooItem;
+
+/// combine edits made to the same list in two places, `mine` and `theirs`, given the `base`
+/// both started from. items are matched by label among their siblings, so a renamed item
+/// counts as one deleted and one added. where both sides changed the same field, mine wins
+pub fn merge(base: &[DooItem], mine: &[DooItem], theirs: &[DooItem]) -> Vec<DooItem> {
+    let base_keys = keys(base);
+    let mine_keys = keys(mine);
+    let theirs_keys = keys(theirs);
+
+    let mut merged: Vec<(Key, DooItem)> = Vec::new();
+    for (key, item) in mine_keys.iter().zip(mine) {
+        let kept = match (find(base, &base_keys, key), find(theirs, &theirs_keys, key)) {
+            (Some(b), Some(t)) => Some(merge_item(Some(b), item, t)),
+            // they deleted it, which only sticks when i left it alone
+            (Some(b), None) => (b != item).then(|| item.clone()),
+            // added on both sides
+            (None, Some(t)) => Some(merge_item(None, item, t)),
+            (None, None) => Some(item.clone()),
+        };
+        if let Some(item) = kept {
+            merged.push((key.clone(), item));
+        }
+    }
+
+    for (i, (key, item)) in theirs_keys.iter().zip(theirs).enumerate() {
+        if find(mine, &mine_keys, key).is_some() {
+            continue;
+        }
+        // i deleted it, which only sticks when they left it alone
+        if find(base, &base_keys, key).is_some_and(|b| b == item) {
+            continue;
+        }
+        // their new items go after whatever came before them on their side
+        let at = match i {
+            0 => 0,
+            _ => merged
+                .iter()
+                .position(|(k, _)| *k == theirs_keys[i - 1])
+                .map_or(merged.len(), |p| p + 1),
+        };
+        merged.insert(at, (key.clone(), item.clone()));
+    }
+
+    merged.into_iter().map(|(_, item)| item).collect()
+}
+
+/// a label and how many siblings before it share that label
+type Key = (String, usize);
+
+fn keys(items: &[DooItem]) -> Vec<Key> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let seen = items[..i].iter().filter(|o| o.label == item.label).count();
+            (item.label.clone(), seen)
+        })
+        .collect()
+}
+
+fn find<'a>(items: &'a [DooItem], keys: &[Key], key: &Key) -> Option<&'a DooItem> {
+    keys.iter().position(|k| k == key).map(|i| &items[i])
+}
+
+fn merge_item(base: Option<&DooItem>, mine: &DooItem, theirs: &DooItem) -> DooItem {
+    let mut item = mine.clone();
+    item.subtasks = merge(
+        base.map_or(&[][..], |b| &b.subtasks),
+        &mine.subtasks,
+        &theirs.subtasks,
+    );
+    // with nothing to compare against, mine wins every field
+    let base = base.unwrap_or(theirs);
+    item.complete = pick(&base.complete, &mine.complete, &theirs.complete);
+    item.completed = pick(&base.completed, &mine.completed, &theirs.completed);
+    item.notes = pick(&base.notes, &mine.notes, &theirs.notes);
+    item.due = pick(&base.due, &mine.due, &theirs.due);
+    item.priority = pick(&base.priority, &mine.priority, &theirs.priority);
+    item.created = pick(&base.created, &mine.created, &theirs.created);
+    item
+}
+
+/// their value unless i changed mine
+fn pick<T: PartialEq + Clone>(base: &T, mine: &T, theirs: &T) -> T {
+    match mine == base {
+        true => theirs.clone(),
+        false => mine.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(label: &str, subtasks: Vec<DooItem>) -> DooItem {
+        let mut item = DooItem::new(String::from(label), false);
+        item.subtasks = subtasks;
+        item
+    }
+
+    /// plan > (design > sketch, build), ship
+    fn base() -> Vec<DooItem> {
+        vec![
+            item(
+                "plan",
+                vec![
+                    item("design", vec![item("sketch", vec![])]),
+                    item("build", vec![]),
+                ],
+            ),
+            item("ship", vec![]),
+        ]
+    }
+
+    fn labels(items: &[DooItem]) -> Vec<&str> {
+        items.iter().map(|i| i.label.as_str()).collect()
+    }
+
+    #[test]
+    fn keeps_edits_from_both_sides() {
+        let mut mine = base();
+        mine[1].mark();
+        mine.push(item("deploy", vec![]));
+
+        let mut theirs = base();
+        theirs[0].subtasks[1].notes = String::from("use the new parts");
+        theirs[0].subtasks[0].subtasks.clear();
+        theirs.insert(0, item("research", vec![]));
+
+        let merged = merge(&base(), &mine, &theirs);
+        assert_eq!(labels(&merged), vec!["research", "plan", "ship", "deploy"]);
+        assert!(merged[2].complete);
+        assert!(merged[1].subtasks[0].subtasks.is_empty());
+        assert_eq!(merged[1].subtasks[1].notes, "use the new parts");
+    }
+
+    #[test]
+    fn the_same_field_changed_on_both_sides_keeps_mine() {
+        let (mut mine, mut theirs) = (base(), base());
+        mine[1].priority = Some('A');
+        theirs[1].priority = Some('C');
+        theirs[1].notes = String::from("friday");
+
+        let merged = merge(&base(), &mine, &theirs);
+        assert_eq!(merged[1].priority, Some('A'));
+        assert_eq!(merged[1].notes, "friday");
+    }
+
+    #[test]
+    fn deletes_stick_only_when_the_other_side_left_the_task_alone() {
+        // deleted by me, untouched by them
+        let mut mine = base();
+        mine.remove(1);
+        assert_eq!(labels(&merge(&base(), &mine, &base())), vec!["plan"]);
+
+        // deleted by me, edited by them
+        let mut theirs = base();
+        theirs[1].priority = Some('A');
+        let merged = merge(&base(), &mine, &theirs);
+        assert_eq!(labels(&merged), vec!["plan", "ship"]);
+        assert_eq!(merged[1].priority, Some('A'));
+
+        // deleted by them, edited by me
+        let mut mine = base();
+        mine[0].subtasks[1].priority = Some('B');
+        let mut theirs = base();
+        theirs[0].subtasks.remove(1);
+        let merged = merge(&base(), &mine, &theirs);
+        assert_eq!(labels(&merged[0].subtasks), vec!["design", "build"]);
+
+        // deleted by them, untouched by me
+        let merged = merge(&base(), &base(), &theirs);
+        assert_eq!(labels(&merged[0].subtasks), vec!["design"]);
+    }
+
+    #[test]
+    fn repeated_labels_are_told_apart_by_order() {
+        let base = vec![item("call", vec![]), item("call", vec![])];
+        let mut mine = base.clone();
+        mine[1].mark();
+        let mut theirs = base.clone();
+        theirs[0].notes = String::from("mom");
+
+        let merged = merge(&base, &mine, &theirs);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(
+            (merged[0].complete, merged[0].notes.as_str()),
+            (false, "mom")
+        );
+        assert_eq!((merged[1].complete, merged[1].notes.as_str()), (true, ""));
+    }
+
+    #[test]
+    fn a_task_added_on_both_sides_appears_once() {
+        let mut mine = base();
+        mine.push(item("party", vec![item("cake", vec![])]));
+        let mut theirs = base();
+        theirs.push(item("party", vec![item("music", vec![])]));
+
+        let merged = merge(&base(), &mine, &theirs);
+        assert_eq!(labels(&merged), vec!["plan", "ship", "party"]);
+        // their first subtask had nothing before it, so it leads
+        assert_eq!(labels(&merged[2].subtasks), vec!["music", "cake"]);
+    }
+}
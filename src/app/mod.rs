@@ -1,44 +1,125 @@
 #![allow(unused_imports)]
 
+mod backup;
+mod command_history;
 mod commands;
+mod completion;
 pub mod doolist;
+mod due;
+pub mod formats;
+mod history;
+mod keys;
+mod line_editor;
 mod lists;
+mod merge;
+mod notes;
 mod queue;
 mod recent_files;
+mod registers;
+mod search;
+mod state;
+pub mod storage;
+mod tags;
 mod ui;
+mod visual;
+mod watch;
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
-use super::{ utils, config };
-use doolist::{DooItem, DooList};
+use super::{config, error, utils};
+use backup::Backups;
+use chrono::Local;
+use command_history::CommandHistory;
+use completion::Completion;
+use doolist::{DooItem, DooList, SortKey};
+use keys::{Action, Chord, Keymap, Lookup};
+use line_editor::LineEditor;
 use lists::*;
-use queue::CappedQueue;
+use notes::NotesEditor;
 use recent_files::RecentFiles;
+use registers::Registers;
+use search::Search;
 use std::{
     collections::VecDeque,
-    error, io,
+    io,
     time::{Duration, Instant},
 };
+use storage::Lists;
+use tags::TagList;
 use tui::{backend::CrosstermBackend, Terminal};
+use visual::Selection;
+use watch::FileWatcher;
 
 pub enum Mode {
     Search,
     Select,
     Command,
     Input,
+    /// picking several tasks to act on at once
+    Visual,
 }
 
 pub enum Screen {
     DooList,
     Help,
     Recents,
+    Backups,
+    Notes,
+    Tags,
+    Lists,
+    /// the open file changed on disk while there were unsaved edits
+    Conflict,
 }
 
-const RECENT_FILES_PATH: &str = "/home/knara/dev/rust/doo/src/recent_files.json";
+pub enum Severity {
+    Info,
+    Error,
+}
+
+/// feedback shown in the input bar until the next key press
+pub struct Message {
+    pub severity: Severity,
+    pub text: String,
+}
+
+impl Message {
+    pub fn info(text: impl ToString) -> Message {
+        Message {
+            severity: Severity::Info,
+            text: text.to_string(),
+        }
+    }
+
+    pub fn error(text: impl ToString) -> Message {
+        Message {
+            severity: Severity::Error,
+            text: text.to_string(),
+        }
+    }
+}
+
+/// every problem found while starting up, in one message so none of them goes unseen
+fn startup_message(errors: &[error::DooError]) -> Option<Message> {
+    match errors {
+        [] => None,
+        [e] => Some(Message::error(e)),
+        _ => Some(Message::error(format!(
+            "{} problems at startup: {}",
+            errors.len(),
+            errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+                .join("; ")
+        ))),
+    }
+}
 
 // TODO: look into error logging
 pub struct App {
@@ -47,103 +128,743 @@ pub struct App {
     mode: Mode,
     doolist: DooList,
     recent_files: RecentFiles,
+    recent_files_path: String,
+    command_history: CommandHistory,
+    command_history_path: String,
+    /// choices for the word being completed in command mode, shown above the input
+    completion: Option<Completion>,
+    backups: Backups,
+    tags: TagList,
+    lists: Lists,
+    notes: Option<NotesEditor>,
     current_path: Option<String>,
-    undo_delete_queue: CappedQueue<DooItem>,
+    /// notices when another program changes the file at `current_path`
+    watcher: Option<FileWatcher>,
+    search: Search,
+    message: Option<Message>,
+    /// the keys of a sequence like 'gg' typed so far
+    pending_keys: Vec<Chord>,
+    keys: Keymap,
+    visual_keys: Keymap,
+    /// the tasks picked in visual mode, kept while a command for them is typed
+    visual: Option<Selection>,
+    /// tasks yanked or deleted, kept while other lists are opened
+    registers: Registers,
+    registers_path: String,
+    theme: ui::theme::Theme,
+    /// where on the terminal doo is drawn
+    window: ui::layout::Window,
+    /// set by ':recent!' and ':backups!' so that picking a file may drop unsaved edits
+    discard_on_switch: bool,
     quit_state: bool,
-    input: String,
+    /// set while a task added with 'a' or 'A' is given its first label
+    naming: bool,
+    input: LineEditor,
 }
 
 impl App {
     pub fn new(filepath: Option<String>, config: config::DooConfig) -> App {
         // app needs its own config file, in addition todo files
+        let recent_files_path = config.recent_files.resolved_path();
+        let (recent_files, recent_files_error) =
+            match RecentFiles::load(&recent_files_path, config.recent_files.capacity) {
+                Ok(recent_files) => (recent_files, None),
+                Err(e) => (
+                    RecentFiles::new(Some(config.recent_files.capacity)),
+                    Some(e),
+                ),
+            };
+
+        let command_history_path = config.command_history.resolved_path();
+        let (command_history, command_history_error) =
+            match CommandHistory::load(&command_history_path, config.command_history.capacity) {
+                Ok(command_history) => (command_history, None),
+                Err(e) => (
+                    CommandHistory::new(config.command_history.capacity),
+                    Some(e),
+                ),
+            };
+
+        let registers_path = config.registers.resolved_path();
+        let (registers, registers_error) = match config.registers.persist {
+            true => match Registers::load(&registers_path) {
+                Ok(registers) => (registers, None),
+                Err(e) => (Registers::default(), Some(e)),
+            },
+            false => (Registers::default(), None),
+        };
+
+        let (keys, keys_error) = match Keymap::new(&config.keys) {
+            Ok(keys) => (keys, None),
+            Err(e) => (Keymap::default(), Some(e)),
+        };
+        let (visual_keys, visual_keys_error) = match Keymap::visual(&config.visual_keys) {
+            Ok(keys) => (keys, None),
+            Err(e) => (
+                Keymap::visual(&Default::default()).expect("the default visual keys are valid"),
+                Some(e),
+            ),
+        };
+
+        let (theme, theme_error) = match ui::theme::Theme::new(&config.theme) {
+            Ok(theme) => (theme, None),
+            Err(e) => (ui::theme::Theme::default(), Some(e)),
+        };
+
+        let (window, layout_error) = match ui::layout::Window::new(&config.layout) {
+            Ok(window) => (window, None),
+            Err(e) => (ui::layout::Window::default(), Some(e)),
+        };
+
+        let storage_error = storage::configured_format(&config.storage).err();
+
+        let mut startup_errors: Vec<error::DooError> = [
+            layout_error,
+            theme_error,
+            keys_error,
+            visual_keys_error,
+            recent_files_error,
+            command_history_error,
+            registers_error,
+            storage_error,
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
         let mut app = App {
+            search: Search::new(&config.search),
+            input: LineEditor::new(config.input.vim),
             config,
             screen: Screen::DooList,
             mode: Mode::Select,
-            input: String::from(""),
             doolist: DooList::new(),
-            recent_files: RecentFiles::load(RECENT_FILES_PATH),
+            recent_files,
+            recent_files_path,
+            command_history,
+            command_history_path,
+            completion: None,
+            backups: Backups::new(),
+            tags: TagList::new(),
+            lists: Lists::new(),
+            notes: None,
             current_path: None,
-            undo_delete_queue: CappedQueue::new(5),
+            watcher: None,
+            message: None,
+            pending_keys: Vec::new(),
+            keys,
+            visual_keys,
+            visual: None,
+            registers,
+            registers_path,
+            theme,
+            window,
+            discard_on_switch: false,
             quit_state: false,
+            naming: false,
         };
 
         let valid_filepath: Option<String> = if let Some(path) = filepath {
-            Some(utils::get_abs_path_from(path))
-        } else if let Ok(path) = app.most_recent_save() {
-            Some(path)
+            Some(storage::resolve(&path))
         } else {
-            None
+            app.most_recent_save().ok()
         };
 
-        commands::load(
-            valid_filepath,
-            &mut app.doolist,
-            &mut app.recent_files,
-            &mut app.current_path,
-        );
+        if valid_filepath.is_some() {
+            if let Err(e) = commands::load(
+                valid_filepath,
+                &mut app.doolist,
+                &mut app.recent_files,
+                &mut app.current_path,
+                &app.config,
+            ) {
+                startup_errors.push(e);
+            }
+        }
+        app.message = startup_message(&startup_errors);
+        app.list_loaded();
         app
     }
 
-    fn most_recent_save(&mut self) -> Result<String, Box<dyn error::Error>> {
-        if let Some(i) = self.recent_files.queue.items.get(0) {
+    /// called whenever `doolist` is replaced by a loaded, restored or new list
+    fn list_loaded(&mut self) {
+        self.doolist.history.set_depth(self.config.history_depth);
+        self.watch();
+    }
+
+    /// watch the file at `current_path`. lists in a database are not watched
+    fn watch(&mut self) {
+        self.watcher = None;
+        let path = match &self.current_path {
+            Some(path) if !path.starts_with(storage::SCHEME) => path.clone(),
+            _ => return,
+        };
+        // a restored backup is not what the file holds, so read the file for the base
+        let base = match self.doolist.modified {
+            true => self.read_disk().map(|d| d.list).unwrap_or_default(),
+            false => self.doolist.list.clone(),
+        };
+        match FileWatcher::new(&path, base) {
+            Ok(watcher) => self.watcher = Some(watcher),
+            Err(e) => self.message = Some(Message::error(e)),
+        }
+    }
+
+    /// the list as the file at `current_path` holds it now
+    fn read_disk(&self) -> error::Result<DooList> {
+        let path = self
+            .current_path
+            .as_ref()
+            .ok_or_else(|| error::DooError::Command(String::from("list has no path yet")))?;
+        storage::open(path, &self.config).load(path)
+    }
+
+    /// note that the list was written to `path`, so the change to it is not taken for
+    /// someone else's
+    fn saved(&mut self, path: &str) {
+        if self.current_path.as_deref() == Some(path) {
+            if let Some(watcher) = self.watcher.as_mut() {
+                watcher.synced(self.doolist.list.clone());
+            }
+        }
+    }
+
+    /// refuse to save over a file that changed on disk since it was read, unless the save was
+    /// forced with '!'
+    fn guard_disk(&self, path: Option<&str>, force: bool) -> error::Result<()> {
+        let saving_over = path.is_none() || path == self.current_path.as_deref();
+        match (&self.watcher, saving_over && !force) {
+            (Some(watcher), true) if watcher.exists() && watcher.changed_on_disk() => {
+                Err(error::DooError::Command(String::from(
+                    "file changed on disk since it was read, ':w!' to overwrite it",
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// pick up changes other programs made to the open file. a clean list is simply reloaded,
+    /// one with unsaved edits asks what to do
+    fn check_disk(&mut self) {
+        // wait until nothing is being typed or picked
+        if !matches!((&self.mode, &self.screen), (Mode::Select, Screen::DooList)) {
+            return;
+        }
+        let watcher = match self.watcher.as_mut() {
+            Some(watcher) => watcher,
+            None => return,
+        };
+        if !watcher.poll() {
+            return;
+        }
+        if !watcher.exists() {
+            // keep the list, saving will bring the file back
+            watcher.synced(self.doolist.list.clone());
+            self.message = Some(Message::error("the open file was removed from disk"));
+            return;
+        }
+
+        match self.doolist.modified {
+            true => self.screen = Screen::Conflict,
+            false => {
+                let result = self.resolve_conflict(KeyCode::Char('r'));
+                self.message = Some(match result {
+                    Ok(()) => Message::info("reloaded, the file changed on disk"),
+                    Err(e) => Message::error(e),
+                });
+            }
+        }
+    }
+
+    /// 'r' takes the file as it is on disk, 'k' keeps the list as it is here and 'm' merges
+    /// the two. each of them can be undone
+    fn resolve_conflict(&mut self, key_code: KeyCode) -> error::Result<()> {
+        let theirs = self.read_disk()?;
+        let base = self
+            .watcher
+            .as_ref()
+            .map(|w| w.base.clone())
+            .unwrap_or_default();
+        match key_code {
+            KeyCode::Char('r') => {
+                self.doolist.name = theirs.name;
+                self.doolist.replace_list(theirs.list.clone());
+                self.doolist.modified = false;
+            }
+            KeyCode::Char('m') => {
+                let merged = merge::merge(&base, &self.doolist.list, &theirs.list);
+                self.doolist.replace_list(merged);
+            }
+            _ => {}
+        }
+        // from here on the file as it is now is what both sides share
+        if let Some(watcher) = self.watcher.as_mut() {
+            watcher.synced(theirs.list);
+        }
+        Ok(())
+    }
+
+    fn most_recent_save(&mut self) -> Result<String, Box<dyn std::error::Error>> {
+        if let Some(i) = self.recent_files.queue.items.front() {
             return Ok(i.to_string());
         }
 
-        return Err("there is no most recent file path".into());
+        Err("there is no most recent file path".into())
+    }
+
+    pub fn handle_quit(&mut self) -> error::Result<()> {
+        let recent_files = self.recent_files.save(&self.recent_files_path);
+        let command_history = self.command_history.save(&self.command_history_path);
+        let registers = match self.config.registers.persist {
+            true => self.registers.save(&self.registers_path),
+            false => Ok(()),
+        };
+        recent_files.and(command_history).and(registers)
     }
 
-    pub fn handle_quit(&mut self) {
-        self.recent_files.save(RECENT_FILES_PATH).unwrap();
+    /// refuse to throw away unsaved edits unless the command was forced with '!'
+    fn guard_unsaved(&self, force: bool) -> error::Result<()> {
+        match self.doolist.modified && !force {
+            true => Err(error::DooError::UnsavedChanges),
+            false => Ok(()),
+        }
+    }
+
+    /// show the outcome of an action in the message area
+    fn report<T>(&mut self, result: error::Result<T>) {
+        if let Err(e) = result {
+            self.message = Some(Message::error(e));
+        }
+    }
+
+    /// run what a key in select mode is bound to
+    fn perform(&mut self, action: Action) {
+        match action {
+            Action::Quit => {
+                let result = self.guard_unsaved(false);
+                self.quit_state = result.is_ok();
+                self.report(result);
+            }
+            Action::Add => {
+                self.doolist.add_from_label(String::from("-- new task --"));
+                self.mode = Mode::Input;
+                self.naming = true;
+            }
+            Action::AddSubtask => match self.doolist.add_subtodo(String::from("-- new task --")) {
+                Ok(()) => {
+                    self.mode = Mode::Input;
+                    self.naming = true;
+                }
+                Err(e) => self.message = Some(Message::error(e)),
+            },
+            Action::Down => self.doolist.next(),
+            Action::Up => self.doolist.previous(),
+            Action::RaisePriority => {
+                let result = self
+                    .doolist
+                    .edit_selection("change priority of", |item| item.raise_priority());
+                self.report(result);
+            }
+            Action::LowerPriority => {
+                let result = self
+                    .doolist
+                    .edit_selection("change priority of", |item| item.lower_priority());
+                self.report(result);
+            }
+            Action::MoveDown => {
+                let result = self.doolist.move_selection_by(1);
+                self.report(result);
+            }
+            Action::MoveUp => {
+                let result = self.doolist.move_selection_by(-1);
+                self.report(result);
+            }
+            Action::MoveTop => {
+                let result = self.doolist.move_selection_to(0);
+                self.report(result);
+            }
+            Action::MoveBottom => {
+                let result = self.doolist.move_selection_to(usize::MAX);
+                self.report(result);
+            }
+            Action::Fold => self.doolist.fold_selection(),
+            Action::Unfold => self.doolist.unfold_selection(),
+            Action::Mark => {
+                let result = self.doolist.mark_selection();
+                self.report(result);
+            }
+            Action::Delete => {
+                if let Some(item) = self.doolist.remove() {
+                    self.registers.store(vec![item]);
+                }
+            }
+            Action::Redo => match self.doolist.redo() {
+                true => {}
+                false => self.message = Some(Message::info("already at newest change")),
+            },
+            Action::Undo => match self.doolist.undo() {
+                true => {}
+                false => self.message = Some(Message::info("already at oldest change")),
+            },
+            Action::Edit => {
+                // start from the current name, so fixing a typo is a small edit
+                if let Some(item) = self.doolist.selected_item() {
+                    self.input.set(&item.label);
+                    self.mode = Mode::Input;
+                }
+            }
+            Action::Notes => {
+                if let Some(path) = self.doolist.selected_path() {
+                    let notes = self.doolist.get(&path).map_or("", |item| &item.notes);
+                    self.notes = Some(NotesEditor::new(path.clone(), notes));
+                    self.screen = Screen::Notes;
+                }
+            }
+            Action::Command => self.mode = Mode::Command,
+            Action::Search => {
+                self.search.origin = self.doolist.state.selected();
+                self.search.clear();
+                self.mode = Mode::Search;
+            }
+            Action::NextMatch => self.jump_to_match(false),
+            Action::PreviousMatch => self.jump_to_match(true),
+            Action::ClearSearch => self.search.clear(),
+            Action::Visual => {
+                self.visual = Some(Selection::new(&self.doolist));
+                self.mode = Mode::Visual;
+            }
+            Action::Toggle => {
+                let mut selection = Selection::new(&self.doolist);
+                selection.set(self.doolist.selected_path().into_iter().collect());
+                self.visual = Some(selection);
+                self.mode = Mode::Visual;
+            }
+            Action::Yank => {
+                let paths: Vec<_> = self.doolist.selected_path().into_iter().collect();
+                self.yank(&paths);
+            }
+            Action::Paste => self.paste(false),
+            Action::PasteBefore => self.paste(true),
+            Action::Register => self.registers.choosing = true,
+            Action::Leave => {}
+        }
+    }
+
+    /// run what a key in visual mode is bound to. marking, deleting, yanking and commands
+    /// end the selection, moves and priorities keep it for another go
+    fn perform_visual(&mut self, action: Action) {
+        let selection = match self.visual.as_mut() {
+            Some(selection) => selection,
+            None => return self.leave_visual(),
+        };
+        let paths = selection.paths(&self.doolist);
+        match action {
+            Action::Down => self.doolist.next(),
+            Action::Up => self.doolist.previous(),
+            Action::Visual => selection.restart(&self.doolist),
+            Action::Toggle => selection.toggle(&self.doolist),
+            Action::RaisePriority => {
+                let result = self
+                    .doolist
+                    .edit_paths("change priority of", &paths, |item| item.raise_priority());
+                self.report(result);
+            }
+            Action::LowerPriority => {
+                let result = self
+                    .doolist
+                    .edit_paths("change priority of", &paths, |item| item.lower_priority());
+                self.report(result);
+            }
+            Action::MoveDown | Action::MoveUp | Action::MoveTop | Action::MoveBottom => {
+                let offset = match action {
+                    Action::MoveDown => 1,
+                    Action::MoveUp => -1,
+                    Action::MoveTop => isize::MIN,
+                    _ => isize::MAX,
+                };
+                match self.doolist.move_paths_by(&paths, offset) {
+                    Ok(moved) => selection.set(moved),
+                    Err(e) => self.message = Some(Message::error(e)),
+                }
+            }
+            Action::Mark => {
+                let result = self.doolist.mark_paths(&paths);
+                self.report(result);
+                self.leave_visual();
+            }
+            Action::Delete => {
+                match self.doolist.remove_paths(&paths) {
+                    Ok(removed) => {
+                        self.message = Some(Message::info(format!(
+                            "deleted {} tasks, 'u' to bring them back",
+                            removed.len()
+                        )));
+                        self.registers.store(removed);
+                    }
+                    Err(e) => self.message = Some(Message::error(e)),
+                }
+                self.leave_visual();
+            }
+            Action::Yank => {
+                self.yank(&paths);
+                self.leave_visual();
+            }
+            Action::Register => self.registers.choosing = true,
+            Action::Command => self.mode = Mode::Command,
+            Action::Leave | Action::ClearSearch => self.leave_visual(),
+            _ => {}
+        }
+    }
+
+    fn leave_visual(&mut self) {
+        self.visual = None;
+        self.mode = Mode::Select;
+    }
+
+    /// copy the items at `paths`, with their notes and subtasks, into the chosen register
+    fn yank(&mut self, paths: &[doolist::ItemPath]) {
+        let items = self.doolist.items_at(paths);
+        match items.is_empty() {
+            true => {
+                self.registers.cancel();
+                self.message = Some(Message::error(error::DooError::NoSelection("yank")));
+            }
+            false => {
+                self.message = Some(Message::info(format!("yanked {} tasks", items.len())));
+                self.registers.store(items);
+            }
+        }
+    }
+
+    /// paste the chosen register below the selected task, or `above` it
+    fn paste(&mut self, above: bool) {
+        match self.registers.get() {
+            Ok(items) => self.doolist.paste(items, above),
+            Err(e) => self.message = Some(Message::error(e)),
+        }
+    }
+
+    /// the key after '"' names a register, any other key than a char drops it
+    fn choose_register(&mut self, key: &KeyEvent) {
+        match key.code {
+            KeyCode::Char(c) => {
+                let result = self.registers.choose(c);
+                self.report(result);
+            }
+            _ => self.registers.cancel(),
+        }
+    }
+
+    /// a register picked with '"' only lasts for the action right after it
+    fn drop_unused_register(&mut self, action: Action) {
+        if !matches!(
+            action,
+            Action::Register | Action::Yank | Action::Delete | Action::Paste | Action::PasteBefore
+        ) {
+            self.registers.cancel();
+        }
+    }
+
+    /// apply `edit` to the tasks picked in visual mode as one undoable change, or to the
+    /// selected task when nothing is picked
+    fn edit_targets<F>(&mut self, action: &'static str, edit: F) -> error::Result<()>
+    where
+        F: FnMut(&mut DooItem),
+    {
+        match self.visual.take() {
+            Some(selection) => {
+                let paths = selection.paths(&self.doolist);
+                self.doolist.edit_paths(action, &paths, edit)
+            }
+            None => self.doolist.edit_selection(action, edit),
+        }
+    }
+
+    /// the action for a key, once it completes a sequence of the keymap for `mode`
+    fn key_action(&mut self, key: &KeyEvent, mode: &Mode) -> Option<Action> {
+        let keys = match mode {
+            Mode::Visual => &self.visual_keys,
+            _ => &self.keys,
+        };
+        self.pending_keys.push(Chord::from_event(key));
+        let mut lookup = keys.lookup(&self.pending_keys);
+        // a key that does not continue the sequence starts over on its own
+        if matches!(lookup, Lookup::None) && self.pending_keys.len() > 1 {
+            self.pending_keys.drain(..self.pending_keys.len() - 1);
+            lookup = keys.lookup(&self.pending_keys);
+        }
+        match lookup {
+            Lookup::Pending => None,
+            Lookup::Action(action) => {
+                self.pending_keys.clear();
+                Some(action)
+            }
+            Lookup::None => {
+                self.pending_keys.clear();
+                None
+            }
+        }
     }
 
     #[inline]
-    pub fn handle_select(&mut self, key_code: crossterm::event::KeyCode) {
+    pub fn handle_visual(&mut self, key: KeyEvent) {
+        if self.registers.choosing {
+            return self.choose_register(&key);
+        }
+        if let Some(action) = self.key_action(&key, &Mode::Visual) {
+            self.drop_unused_register(action);
+            self.perform_visual(action);
+        }
+    }
+
+    #[inline]
+    pub fn handle_select(&mut self, key: KeyEvent) {
+        let key_code = key.code;
+        // the pickers move with whatever keys move through the list
+        let picker_code = match self.keys.action(&key) {
+            Some(Action::Down) => KeyCode::Down,
+            Some(Action::Up) => KeyCode::Up,
+            _ => key.code,
+        };
         match self.screen {
-            Screen::DooList => match key_code {
-                KeyCode::Char('q') => self.quit_state = true,
-                KeyCode::Char('a') => {
-                    self.doolist.add_from_label(String::from("-- new task --"));
-                    self.undo_delete_queue.clear().unwrap();
-                    self.mode = Mode::Input;
+            Screen::DooList => {
+                if self.registers.choosing {
+                    return self.choose_register(&key);
                 }
-                KeyCode::Char('j') => self.doolist.next(),
-                KeyCode::Char('k') => self.doolist.previous(),
-                KeyCode::Char('x') => {
-                    self.doolist.mark_selection(); // TODO: this should eventually print to an error
-                                                   // message widget
-                    return;
+                if let Some(action) = self.key_action(&key, &Mode::Select) {
+                    self.drop_unused_register(action);
+                    self.perform(action);
                 }
-                KeyCode::Char('d') => {
-                    let deleted_item: Option<DooItem> = self.doolist.remove();
-                    if let Some(item) = deleted_item {
-                        self.undo_delete_queue.push_front(item);
-                    };
+            }
+            Screen::Help => {
+                if key_code == KeyCode::Esc {
+                    self.screen = Screen::DooList
+                }
+            }
+            Screen::Recents => match picker_code {
+                KeyCode::Down => self.recent_files.next(),
+                KeyCode::Up => self.recent_files.previous(),
+                KeyCode::Enter => {
+                    if let Err(e) = self.guard_unsaved(self.discard_on_switch) {
+                        self.message = Some(Message::error(e));
+                        return;
+                    }
+                    let selected_path = self.recent_files.select();
+                    match commands::load(
+                        selected_path,
+                        &mut self.doolist,
+                        &mut self.recent_files,
+                        &mut self.current_path,
+                        &self.config,
+                    ) {
+                        Ok(_) => {
+                            self.list_loaded();
+                            self.screen = Screen::DooList;
+                        }
+                        Err(e) => self.message = Some(Message::error(e)),
+                    }
                 }
-                KeyCode::Char('u') => match self.undo_delete_queue.pop_front() {
-                    Some(item) => self.doolist.add_from_item(item),
-                    None => {}
-                },
-                KeyCode::Char('i') => self.mode = Mode::Input,
-                KeyCode::Char(':') => self.mode = Mode::Command,
+                KeyCode::Esc => self.screen = Screen::DooList,
                 _ => {}
             },
-            Screen::Help => match key_code {
+            Screen::Notes => {
+                let editor = match self.notes.as_mut() {
+                    Some(editor) => editor,
+                    None => {
+                        self.screen = Screen::DooList;
+                        return;
+                    }
+                };
+                let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                let alt = key.modifiers.contains(KeyModifiers::ALT);
+                match key_code {
+                    KeyCode::Esc => {
+                        let result = self.doolist.set_notes_at(&editor.path, editor.text());
+                        self.notes = None;
+                        self.screen = Screen::DooList;
+                        self.report(result);
+                    }
+                    // ctrl-c closes the notes without keeping the edits
+                    KeyCode::Char('c') if ctrl => {
+                        self.notes = None;
+                        self.screen = Screen::DooList;
+                    }
+                    KeyCode::Char(c) if !ctrl && !alt => editor.insert(c),
+                    KeyCode::Enter => editor.newline(),
+                    KeyCode::Backspace => editor.backspace(),
+                    KeyCode::Left => editor.left(),
+                    KeyCode::Right => editor.right(),
+                    KeyCode::Up => editor.up(),
+                    KeyCode::Down => editor.down(),
+                    _ => {}
+                }
+            }
+            Screen::Backups => match picker_code {
+                KeyCode::Down => self.backups.next(),
+                KeyCode::Up => self.backups.previous(),
+                KeyCode::Enter => {
+                    if let Err(e) = self.guard_unsaved(self.discard_on_switch) {
+                        self.message = Some(Message::error(e));
+                        return;
+                    }
+                    match commands::restore(
+                        self.backups.select(),
+                        &self.current_path,
+                        &mut self.doolist,
+                        &self.config,
+                    ) {
+                        Ok(path) => {
+                            self.list_loaded();
+                            self.message =
+                                Some(Message::info(format!("restored {}, ':w' to keep it", path)));
+                            self.screen = Screen::DooList;
+                        }
+                        Err(e) => self.message = Some(Message::error(e)),
+                    }
+                }
                 KeyCode::Esc => self.screen = Screen::DooList,
                 _ => {}
             },
-            Screen::Recents => match key_code {
-                KeyCode::Char('j') => self.recent_files.next(),
-                KeyCode::Char('k') => self.recent_files.previous(),
+            Screen::Lists => match picker_code {
+                KeyCode::Down => self.lists.next(),
+                KeyCode::Up => self.lists.previous(),
                 KeyCode::Enter => {
-                    let selected_path = self.recent_files.select();
-                    commands::load(
-                        selected_path,
+                    if let Err(e) = self.guard_unsaved(self.discard_on_switch) {
+                        self.message = Some(Message::error(e));
+                        return;
+                    }
+                    match commands::load(
+                        self.lists.select(),
                         &mut self.doolist,
                         &mut self.recent_files,
                         &mut self.current_path,
-                    );
-                    // BUG: should only return to doolist on load success
+                        &self.config,
+                    ) {
+                        Ok(_) => {
+                            self.list_loaded();
+                            self.screen = Screen::DooList;
+                        }
+                        Err(e) => self.message = Some(Message::error(e)),
+                    }
+                }
+                KeyCode::Esc => self.screen = Screen::DooList,
+                _ => {}
+            },
+            Screen::Conflict => match key_code {
+                KeyCode::Char('r') | KeyCode::Char('k') | KeyCode::Char('m') | KeyCode::Esc => {
+                    let result = self.resolve_conflict(key_code);
+                    self.screen = Screen::DooList;
+                    self.report(result);
+                }
+                _ => {}
+            },
+            Screen::Tags => match picker_code {
+                KeyCode::Down => self.tags.next(),
+                KeyCode::Up => self.tags.previous(),
+                KeyCode::Enter => {
+                    if let Some(tag) = self.tags.select() {
+                        self.doolist.set_filter(vec![tag]);
+                    }
                     self.screen = Screen::DooList;
                 }
                 KeyCode::Esc => self.screen = Screen::DooList,
@@ -153,106 +874,380 @@ impl App {
     }
 
     #[inline]
-    pub fn handle_search(&mut self, _key_code: crossterm::event::KeyCode) {
-        todo!()
-    }
-
-    #[inline]
-    pub fn handle_command_input(&mut self, key_code: crossterm::event::KeyCode) {
-        match key_code {
+    pub fn handle_search(&mut self, key: KeyEvent) {
+        match key.code {
             KeyCode::Enter => {
                 self.mode = Mode::Select;
-                self.run_input_command(self.input.clone());
                 self.input.clear();
             }
             KeyCode::Esc => {
                 self.mode = Mode::Select;
+                self.search.clear();
+                self.doolist.state.select(self.search.origin);
                 self.input.clear();
             }
-            KeyCode::Char(c) => {
-                self.input.push(c);
-            }
-            KeyCode::Backspace => {
-                self.input.pop();
+            _ => {
+                if self.input.handle(key) {
+                    self.update_search();
+                }
             }
-            _ => {}
+        }
+    }
+
+    /// re-run the query as it is typed, moving to the first match below where the search
+    /// started
+    fn update_search(&mut self) {
+        self.search.set_query(self.input.text().to_string());
+        let from = self.search.origin.unwrap_or(0);
+        match self.search.first_match_from(&self.doolist, from) {
+            Some(path) => self.doolist.select_path(&path),
+            None => self.doolist.state.select(self.search.origin),
+        }
+    }
+
+    fn jump_to_match(&mut self, reverse: bool) {
+        if let Some(path) = self.search.next_match(&self.doolist, reverse) {
+            self.doolist.select_path(&path);
         }
     }
 
     #[inline]
-    pub fn handle_label_input(&mut self, key_code: crossterm::event::KeyCode) {
-        match key_code {
+    pub fn handle_command_input(&mut self, key: KeyEvent) {
+        // any key but tab settles the completion
+        if !matches!(key.code, KeyCode::Tab | KeyCode::BackTab) {
+            self.completion = None;
+        }
+        match key.code {
             KeyCode::Enter => {
                 self.mode = Mode::Select;
-                if let Err(e) = self.doolist.change_label_name(self.input.clone()) {
-                    eprintln!("{}", e);
-                }
+                let line = self.input.text().to_string();
+                self.command_history.add(&line);
+                self.run_input_command(line);
+                // commands that do not act on the picked tasks still end the selection
+                self.visual = None;
                 self.input.clear();
             }
-            KeyCode::Esc => {
+            KeyCode::Up => {
+                if let Some(line) = self.command_history.older(self.input.text()) {
+                    self.input.set(&line);
+                }
+            }
+            KeyCode::Down => {
+                if let Some(line) = self.command_history.newer() {
+                    self.input.set(&line);
+                }
+            }
+            KeyCode::Tab => self.complete(false),
+            KeyCode::BackTab => self.complete(true),
+            _ => {
+                // esc first leaves for normal mode when vim input is on
+                if !self.input.handle(key) && key.code == KeyCode::Esc {
+                    // back to picking, when the command was for the picked tasks
+                    self.mode = match self.visual {
+                        Some(_) => Mode::Visual,
+                        None => Mode::Select,
+                    };
+                    self.command_history.reset();
+                    self.input.clear();
+                }
+            }
+        }
+    }
+
+    /// complete the last word of the command line. the first tab completes as far as every
+    /// choice agrees, the next ones cycle through the choices
+    fn complete(&mut self, back: bool) {
+        if let Some(completion) = self.completion.as_mut() {
+            completion.cycle(back);
+            if let Some(i) = completion.selected {
+                let line = completion.apply(self.input.text(), &completion.candidates[i]);
+                self.input.set(&line);
+            }
+            return;
+        }
+
+        if let Some(completion) = Completion::new(self.input.text()) {
+            let line = completion.apply(self.input.text(), &completion.common_prefix());
+            self.input.set(&line);
+            // a single choice is done with, several are shown to pick from
+            if completion.candidates.len() > 1 {
+                self.completion = Some(completion);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn handle_label_input(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => {
                 self.mode = Mode::Select;
+                let (label, due) = due::extract(self.input.text(), Local::now().naive_local());
+                // a task just added with 'a' or 'A' takes the tags of the filter, and naming it
+                // folds into the add so that one undo removes it
+                let label = match self.naming {
+                    true => self.doolist.label_for_selection(label),
+                    false => label,
+                };
+                let edit = |item: &mut DooItem| {
+                    item.change_label(label);
+                    if due.is_some() {
+                        item.due = due;
+                    }
+                };
+                let result = match self.naming {
+                    true => self.doolist.edit_added(edit),
+                    false => self.doolist.edit_selection("change label name", edit),
+                };
+                self.report(result);
                 self.input.clear();
+                self.naming = false;
             }
-            KeyCode::Char(c) => {
-                self.input.push(c);
-            }
-            KeyCode::Backspace => {
-                self.input.pop();
+            _ => {
+                if !self.input.handle(key) && key.code == KeyCode::Esc {
+                    self.mode = Mode::Select;
+                    self.input.clear();
+                    self.naming = false;
+                }
             }
-            _ => {}
         }
     }
 
     fn run_input_command(&mut self, input: String) {
+        match self.execute_command(input) {
+            Ok(Some(info)) => self.message = Some(Message::info(info)),
+            Ok(None) => {}
+            Err(e) => self.message = Some(Message::error(e)),
+        }
+    }
+
+    /// run a command line, returning any info worth showing to the user
+    fn execute_command(&mut self, input: String) -> error::Result<Option<String>> {
         let elements: Vec<&str> = input.split(' ').collect();
-        match elements[0] {
-            "save" => commands::saveas(
-                None,
-                &mut self.doolist,
-                &mut self.recent_files,
-                &self.current_path,
-            ),
-            "saveas" | "w" => commands::saveas(
-                match elements.get(1) {
-                    Some(i) => Some(utils::get_abs_path_from(i.to_string())),
-                    None => None,
-                },
-                &mut self.doolist,
-                &mut self.recent_files,
-                &self.current_path,
-            ),
-            "load" | "e" => match elements.get(1) {
-                Some(i) => commands::load(
-                    Some(utils::get_abs_path_from(i.to_string())),
+        let (command, force) = match elements[0].strip_suffix('!') {
+            Some(command) => (command, true),
+            None => (elements[0], false),
+        };
+
+        match command {
+            "save" | "saveas" | "w" => {
+                let path = match command {
+                    "save" => None,
+                    _ => elements.get(1).map(|i| storage::resolve(i)),
+                };
+                self.guard_disk(path.as_deref(), force)?;
+                let unnamed = self.current_path.is_none();
+                let path = commands::saveas(
+                    path,
                     &mut self.doolist,
                     &mut self.recent_files,
                     &mut self.current_path,
-                ),
-                None => eprintln!("failed to load file with supplied path"),
-            },
+                    &self.config,
+                )?;
+                match (unnamed, self.current_path.as_deref() == Some(path.as_str())) {
+                    (true, _) => self.watch(),
+                    (false, true) => self.saved(&path),
+                    (false, false) => return Ok(Some(format!("saved a copy to {}", path))),
+                }
+                Ok(Some(format!("saved to {}", path)))
+            }
+            "load" | "e" => {
+                self.guard_unsaved(force)?;
+                let path = commands::load(
+                    elements.get(1).map(|i| storage::resolve(i)),
+                    &mut self.doolist,
+                    &mut self.recent_files,
+                    &mut self.current_path,
+                    &self.config,
+                )?;
+                self.list_loaded();
+                Ok(Some(format!("loaded {}", path)))
+            }
+            "export" => {
+                let path = elements
+                    .get(2)
+                    .map(|i| utils::get_abs_path_from(utils::expand_home(i)));
+                commands::export(elements.get(1), path, &self.doolist, self.config.backups)
+                    .map(|path| Some(format!("exported to {}", path)))
+            }
+            "import" => {
+                let path = elements
+                    .get(1)
+                    .map(|i| utils::get_abs_path_from(utils::expand_home(i)));
+                commands::import(path, &mut self.doolist)
+                    .map(|count| Some(format!("imported {} tasks", count)))
+            }
             "wq" => {
+                self.guard_disk(None, force)?;
                 commands::saveas(
                     None,
                     &mut self.doolist,
                     &mut self.recent_files,
-                    &self.current_path,
-                );
+                    &mut self.current_path,
+                    &self.config,
+                )?;
                 commands::quit(&mut self.quit_state);
+                Ok(None)
+            }
+            "new" => {
+                self.guard_unsaved(force)?;
+                commands::new(&mut self.doolist, &mut self.current_path);
+                self.list_loaded();
+                Ok(None)
+            }
+            "rename" => commands::rename(elements.get(1), &mut self.doolist).map(|_| None),
+            "help" => {
+                commands::help(&mut self.screen, &mut self.mode);
+                Ok(None)
+            }
+            "recent" => {
+                self.discard_on_switch = force;
+                commands::recent(&mut self.screen, &mut self.mode);
+                Ok(None)
             }
-            "new" => commands::new(&mut self.doolist, &mut self.current_path),
-            "rename" => commands::rename(elements.get(1), &mut self.doolist.name),
-            "help" => commands::help(&mut self.screen, &mut self.mode),
-            "recent" => commands::recent(&mut self.screen, &mut self.mode),
-            "path" => eprintln!(
-                "{}",
-                if let Some(i) = &self.current_path {
-                    i
-                } else {
-                    "xd"
+            "backups" => {
+                self.discard_on_switch = force;
+                commands::backups(
+                    &self.current_path,
+                    self.config.backups,
+                    &mut self.backups,
+                    &mut self.screen,
+                    &mut self.mode,
+                )
+                .map(|_| None)
+            }
+            "filter" => {
+                let filter = elements[1..]
+                    .iter()
+                    .filter(|t| !t.is_empty())
+                    .map(|t| match tags::is_tag(t) {
+                        true => Ok(t.to_lowercase()),
+                        false => Err(error::DooError::Command(format!(
+                            "not a #tag or @context: {}",
+                            t
+                        ))),
+                    })
+                    .collect::<error::Result<Vec<String>>>()?;
+                self.doolist.set_filter(filter);
+                Ok(None)
+            }
+            "lists" => {
+                // the lists next to the open one, or in the given directory or database
+                let location = match (elements.get(1), &self.current_path) {
+                    (Some(location), _) => storage::resolve(location),
+                    (None, Some(path)) => path.clone(),
+                    (None, None) => storage::resolve("."),
+                };
+                self.lists = Lists::find(&location, &self.config)?;
+                if self.lists.items.is_empty() {
+                    return Err(error::DooError::Command(format!(
+                        "no lists in {}",
+                        location
+                    )));
                 }
-            ),
-            "q" => commands::quit(&mut self.quit_state),
-            _ => {}
+                self.discard_on_switch = force;
+                self.screen = Screen::Lists;
+                self.mode = Mode::Select;
+                Ok(None)
+            }
+            "tags" => {
+                self.tags = TagList::find(&self.doolist);
+                if self.tags.items.is_empty() {
+                    return Err(error::DooError::Command(String::from(
+                        "no #tags or @contexts in this list",
+                    )));
+                }
+                self.screen = Screen::Tags;
+                Ok(None)
+            }
+            "m" | "move" => {
+                let to = elements
+                    .get(1)
+                    .and_then(|to| to.parse::<usize>().ok())
+                    .ok_or_else(|| error::DooError::Command(String::from("usage: ':m <n>'")))?;
+                // positions on screen start at 1, ':m 0' also means the top
+                self.doolist
+                    .move_selection_to(to.saturating_sub(1))
+                    .map(|_| None)
+            }
+            "priority" | "pri" => {
+                let priority = elements
+                    .get(1)
+                    .and_then(|p| doolist::parse_priority(p))
+                    .ok_or_else(|| {
+                        error::DooError::Command(String::from(
+                            "usage: ':priority <A-Z|high|med|low|none>'",
+                        ))
+                    })?;
+                self.edit_targets("change priority of", |item| item.priority = priority)
+                    .map(|_| None)
+            }
+            "sort" => {
+                let key = elements
+                    .get(1)
+                    .and_then(|key| SortKey::parse(key))
+                    .ok_or_else(|| {
+                        error::DooError::Command(String::from(
+                            "usage: ':sort <priority|due|done|label>'",
+                        ))
+                    })?;
+                self.doolist.sort(key);
+                Ok(None)
+            }
+            "due" => {
+                let input = elements[1..].join(" ");
+                let due = match input.is_empty() {
+                    true => None,
+                    false => Some(due::parse(&input, Local::now().naive_local()).ok_or_else(
+                        || error::DooError::Command(format!("not a due date: {}", input)),
+                    )?),
+                };
+                self.edit_targets("set a due date for", |item| item.due = due)
+                    .map(|_| None)
+            }
+            "tag" => {
+                let tags = elements[1..]
+                    .iter()
+                    .filter(|t| !t.is_empty())
+                    .map(|t| match tags::is_tag(t) {
+                        true => Ok(t.to_string()),
+                        false => Err(error::DooError::Command(format!(
+                            "not a #tag or @context: {}",
+                            t
+                        ))),
+                    })
+                    .collect::<error::Result<Vec<String>>>()?;
+                if tags.is_empty() {
+                    return Err(error::DooError::Command(String::from(
+                        "usage: ':tag <#tag|@context>...'",
+                    )));
+                }
+                self.edit_targets("tag", |item| {
+                    let missing: Vec<&str> = tags
+                        .iter()
+                        .filter(|t| !item.tags.contains(&t.to_lowercase()))
+                        .map(|t| t.as_str())
+                        .collect();
+                    if !missing.is_empty() {
+                        item.change_label(format!("{} {}", item.label, missing.join(" ")));
+                    }
+                })
+                .map(|_| None)
+            }
+            "registers" | "reg" => Ok(Some(self.registers.summary())),
+            "path" => Ok(Some(match &self.current_path {
+                Some(path) => path.to_string(),
+                None => String::from("list has no path yet"),
+            })),
+            "q" => {
+                self.guard_unsaved(force)?;
+                commands::quit(&mut self.quit_state);
+                Ok(None)
+            }
+            "" => Ok(None),
+            _ => Err(error::DooError::Command(format!(
+                "not a command: {}",
+                elements[0]
+            ))),
         }
     }
 }
@@ -275,19 +1270,21 @@ pub fn run(mut app: App) -> Result<(), io::Error> {
             .unwrap_or_else(|| Duration::from_secs(0));
         if crossterm::event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
+                app.message = None;
                 match app.mode {
-                    Mode::Select => app.handle_select(key.code),
-                    Mode::Search => app.handle_search(key.code),
-                    Mode::Command => app.handle_command_input(key.code),
-                    Mode::Input => app.handle_label_input(key.code),
+                    Mode::Select => app.handle_select(key),
+                    Mode::Search => app.handle_search(key),
+                    Mode::Command => app.handle_command_input(key),
+                    Mode::Input => app.handle_label_input(key),
+                    Mode::Visual => app.handle_visual(key),
                 }
             }
         }
         if last_tick.elapsed() >= tick_rate {
+            app.check_disk();
             last_tick = Instant::now();
         }
         if app.quit_state {
-            app.handle_quit();
             break;
         }
     }
@@ -301,5 +1298,136 @@ pub fn run(mut app: App) -> Result<(), io::Error> {
     )?;
     term.show_cursor()?;
 
+    // the terminal is back to normal, so failures here can go straight to stderr
+    if let Err(e) = app.handle_quit() {
+        eprintln!("doo: {}", e);
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// a fresh directory for one test, so app state never touches the user's files
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("doo-app-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn app_in(dir: &std::path::Path, config: config::DooConfig) -> App {
+        open_in(dir, None, config)
+    }
+
+    fn open_in(dir: &std::path::Path, file: Option<&str>, config: config::DooConfig) -> App {
+        let mut config = config;
+        let state = |file: &str| Some(dir.join(file).to_string_lossy().to_string());
+        config.recent_files.path = state("recent_files.json");
+        config.command_history.path = state("command_history.json");
+        config.registers.path = state("registers.json");
+        App::new(file.and_then(state), config)
+    }
+
+    fn error_text(app: &App) -> Option<&str> {
+        match &app.message {
+            Some(Message {
+                severity: Severity::Error,
+                text,
+            }) => Some(text),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn a_clean_start_shows_no_message() {
+        let dir = scratch("clean");
+        let app = app_in(&dir, config::DooConfig::default());
+        assert!(app.message.is_none());
+    }
+
+    #[test]
+    fn a_single_startup_error_is_shown_as_it_is() {
+        let dir = scratch("one-error");
+        let mut config = config::DooConfig::default();
+        config.theme.preset = String::from("neon");
+        let app = app_in(&dir, config);
+        let text = error_text(&app).unwrap();
+        assert!(text.starts_with("config: ") && text.contains("neon"));
+        // the broken section falls back to its default rather than stopping doo
+        assert_eq!(app.theme.text, ui::theme::Theme::default().text);
+    }
+
+    #[test]
+    fn every_startup_error_is_shown_together() {
+        let dir = scratch("errors");
+        std::fs::write(dir.join("registers.json"), "not json").unwrap();
+        std::fs::write(dir.join("list.json"), "{ broken").unwrap();
+        let config = config::DooConfig {
+            storage: String::from("yaml"),
+            keys: [(String::from("x"), String::from("fly"))].into(),
+            registers: config::RegistersConfig {
+                persist: true,
+                path: None,
+            },
+            ..Default::default()
+        };
+
+        let app = open_in(&dir, Some("list.json"), config);
+        let text = error_text(&app).unwrap();
+        assert!(text.starts_with("4 problems at startup: "), "{}", text);
+        for part in ["fly", "registers.json", "yaml", "list.json"] {
+            assert!(text.contains(part), "{} missing from {}", part, text);
+        }
+    }
+
+    #[test]
+    fn writing_a_copy_elsewhere_keeps_the_open_file_unsaved() {
+        let dir = scratch("saveas");
+        let (first, copy) = (dir.join("first.json"), dir.join("copy.json"));
+        let mut app = app_in(&dir, config::DooConfig::default());
+
+        app.doolist.add_from_label(String::from("plan"));
+        app.execute_command(format!("w {}", first.display()))
+            .unwrap();
+        assert_eq!(app.current_path.as_deref(), first.to_str());
+        assert!(!app.doolist.modified);
+
+        app.doolist.add_from_label(String::from("ship"));
+        app.execute_command(format!("w {}", copy.display()))
+            .unwrap();
+        assert_eq!(app.current_path.as_deref(), first.to_str());
+        assert!(app.doolist.modified);
+        assert!(matches!(
+            app.execute_command(String::from("q")),
+            Err(error::DooError::UnsavedChanges)
+        ));
+
+        app.execute_command(String::from("w")).unwrap();
+        assert!(app.execute_command(String::from("q")).is_ok());
+    }
+
+    #[test]
+    fn notes_take_plain_chars_and_can_be_dropped() {
+        let dir = scratch("notes");
+        let mut app = app_in(&dir, config::DooConfig::default());
+        app.doolist.add_from_label(String::from("plan"));
+        let press = |c: char, modifiers: KeyModifiers| KeyEvent::new(KeyCode::Char(c), modifiers);
+
+        app.perform(Action::Notes);
+        app.handle_select(press('a', KeyModifiers::NONE));
+        app.handle_select(press('s', KeyModifiers::CONTROL));
+        app.handle_select(press('b', KeyModifiers::ALT));
+        app.handle_select(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(app.doolist.list[0].notes, "a");
+
+        app.perform(Action::Notes);
+        app.handle_select(press('b', KeyModifiers::NONE));
+        app.handle_select(press('c', KeyModifiers::CONTROL));
+        assert!(app.notes.is_none());
+        assert_eq!(app.doolist.list[0].notes, "a");
+    }
+}
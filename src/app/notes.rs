@@ -0,0 +1,197 @@
+use super::doolist::ItemPath;
+use unicode_width::UnicodeWidthStr;
+
+/// a small multi-line editor for the notes of a single item
+pub struct NotesEditor {
+    /// the item whose notes are being edited
+    pub path: ItemPath,
+    pub lines: Vec<String>,
+    pub row: usize,
+    /// cursor position within the row, counted in chars
+    pub col: usize,
+}
+
+impl NotesEditor {
+    pub fn new(path: ItemPath, notes: &str) -> NotesEditor {
+        let mut lines: Vec<String> = notes.split('\n').map(String::from).collect();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        let row = lines.len() - 1;
+        let col = lines[row].chars().count();
+
+        NotesEditor {
+            path,
+            lines,
+            row,
+            col,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n").trim_end().to_string()
+    }
+
+    fn byte_index(&self) -> usize {
+        let line = &self.lines[self.row];
+        line.char_indices()
+            .nth(self.col)
+            .map_or(line.len(), |(i, _)| i)
+    }
+
+    /// how many columns the text before the cursor takes up on screen
+    pub fn cursor_width(&self) -> usize {
+        self.lines[self.row][..self.byte_index()].width()
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    pub fn insert(&mut self, c: char) {
+        let i = self.byte_index();
+        self.lines[self.row].insert(i, c);
+        self.col += 1;
+    }
+
+    pub fn newline(&mut self) {
+        let i = self.byte_index();
+        let rest = self.lines[self.row].split_off(i);
+        self.row += 1;
+        self.lines.insert(self.row, rest);
+        self.col = 0;
+    }
+
+    pub fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            let i = self.byte_index();
+            self.lines[self.row].remove(i);
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    pub fn left(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.line_len();
+        }
+    }
+
+    pub fn right(&mut self) {
+        if self.col < self.line_len() {
+            self.col += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    pub fn up(&mut self) {
+        if self.row > 0 {
+            self.row -= 1;
+            self.col = self.col.min(self.line_len());
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = self.col.min(self.line_len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(editor: &mut NotesEditor, text: &str) {
+        for c in text.chars() {
+            match c {
+                '\n' => editor.newline(),
+                c => editor.insert(c),
+            }
+        }
+    }
+
+    #[test]
+    fn opens_with_the_cursor_at_the_end() {
+        let editor = NotesEditor::new(vec![0, 1], "first\nsecond");
+        assert_eq!((editor.row, editor.col), (1, 6));
+        assert_eq!(editor.path, vec![0, 1]);
+
+        let empty = NotesEditor::new(vec![0], "");
+        assert_eq!(empty.lines, vec![""]);
+        assert_eq!((empty.row, empty.col), (0, 0));
+        assert_eq!(empty.text(), "");
+    }
+
+    #[test]
+    fn newline_and_backspace_split_and_join_lines() {
+        let mut editor = NotesEditor::new(vec![0], "call mom");
+        editor.left();
+        editor.left();
+        editor.left();
+        editor.newline();
+        assert_eq!(editor.lines, vec!["call ", "mom"]);
+        assert_eq!((editor.row, editor.col), (1, 0));
+
+        editor.backspace();
+        assert_eq!(editor.lines, vec!["call mom"]);
+        assert_eq!((editor.row, editor.col), (0, 5));
+        // nothing before the start to remove
+        let mut start = NotesEditor::new(vec![0], "");
+        start.backspace();
+        assert_eq!(start.lines, vec![""]);
+    }
+
+    #[test]
+    fn the_cursor_wraps_between_lines_and_keeps_to_short_ones() {
+        let mut editor = NotesEditor::new(vec![0], "a long line\nab\nanother line");
+        editor.up();
+        assert_eq!((editor.row, editor.col), (1, 2));
+        editor.up();
+        assert_eq!((editor.row, editor.col), (0, 2));
+        editor.up();
+        assert_eq!(editor.row, 0);
+
+        editor.left();
+        editor.left();
+        editor.left();
+        assert_eq!((editor.row, editor.col), (0, 0));
+        for _ in 0..12 {
+            editor.right();
+        }
+        assert_eq!((editor.row, editor.col), (1, 0));
+        editor.left();
+        assert_eq!((editor.row, editor.col), (0, 11));
+
+        editor.down();
+        editor.down();
+        editor.down();
+        assert_eq!((editor.row, editor.col), (2, 2));
+    }
+
+    #[test]
+    fn wide_chars_are_edited_whole_and_trailing_blank_lines_dropped() {
+        let mut editor = NotesEditor::new(vec![0], "");
+        typed(&mut editor, "牛奶\n\n");
+        editor.up();
+        editor.up();
+        editor.right();
+        editor.backspace();
+        assert_eq!(editor.lines[0], "奶");
+        typed(&mut editor, "酸");
+        assert_eq!(editor.text(), "酸奶");
+        // each of them takes two columns on screen
+        assert_eq!(editor.col, 1);
+        assert_eq!(editor.cursor_width(), 2);
+    }
+}
@@ -1,4 +1,3 @@
-use super::doolist::DooItem;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
@@ -23,23 +22,14 @@ impl<T> CappedQueue<T> {
             return Ok(());
         }
 
-        return Err("Queue has reached max capacity, cannot push items to queue");
-    }
-
-    pub fn pop_front(&mut self) -> Option<T> {
-        return self.items.pop_front();
+        Err("Queue has reached max capacity, cannot push items to queue")
     }
 
     pub fn pop_back(&mut self) -> Option<T> {
-        return self.items.pop_back();
+        self.items.pop_back()
     }
 
     pub fn is_full(&self) -> bool {
-        return self.items.len() == self.capacity;
-    }
-
-    pub fn clear(&mut self) -> Result<(), &'static str> {
-        self.items.clear();
-        Ok(())
+        self.items.len() == self.capacity
     }
 }
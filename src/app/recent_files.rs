@@ -1,11 +1,11 @@
-use super::{super::utils, lists::Navigate, queue::CappedQueue};
+use super::{super::utils, lists::Navigate, queue::CappedQueue, state};
+use crate::error::Result;
 use path_clean::PathClean;
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::env;
 use std::error::Error;
-use std::fs::{File, OpenOptions};
-use std::io::{self, BufReader, BufWriter};
+use std::io::{self, BufReader};
 use std::path::Path;
 use tui::widgets::ListState;
 
@@ -20,60 +20,36 @@ pub struct RecentFiles {
 impl RecentFiles {
     pub fn new(capacity: Option<usize>) -> RecentFiles {
         RecentFiles {
-            queue: CappedQueue::new(match capacity {
-                Some(i) => i,
-                None => 5,
-            }),
+            queue: CappedQueue::new(capacity.unwrap_or(5)),
             state: ListState::default(),
         }
     }
 
-    // TODO: impl error handling for this function
-    pub fn load(path: &str) -> RecentFiles {
-        let file = File::open(path).expect("failed to open file");
-        let reader = BufReader::new(file);
-        let list: serde_json::Result<RecentFiles> = serde_json::from_reader(reader);
-
-        if let Ok(mut recent_files) = list {
-            if recent_files.queue.items.len() > 0 {
-                recent_files.next();
-            }
-            return recent_files;
-        } else {
-            eprintln!("failed to load RecentFiles file");
-            return RecentFiles::new(Some(5));
+    /// load the recent files list at `path`, starting an empty one if it does not exist yet
+    pub fn load(path: &str, capacity: usize) -> Result<RecentFiles> {
+        let mut recent_files: RecentFiles = match state::load(path)? {
+            Some(recent_files) => recent_files,
+            None => return Ok(RecentFiles::new(Some(capacity))),
+        };
+
+        // the configured capacity wins over whatever was stored
+        recent_files.queue.capacity = capacity;
+        recent_files.queue.items.truncate(capacity);
+        if !recent_files.queue.items.is_empty() {
+            recent_files.next();
         }
+        Ok(recent_files)
     }
 
-    pub fn save(&self, path: &str) -> std::io::Result<()> {
-        eprintln!("attempting to save...");
-
-        let file = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .create(true)
-            .open(path)
-            .expect("unable to open file");
-
-        let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, &self)?;
-
-        Ok(())
+    pub fn save(&self, path: &str) -> Result<()> {
+        state::save(path, self)
     }
 
     pub fn select(&mut self) -> Option<String> {
         let mut ret = None;
 
         if let Some(i) = self.state.selected() {
-            match self.queue.items.get(i) {
-                Some(path) => {
-                    ret = Some(path.to_string());
-                }
-                None => {
-                    eprintln!("failed to get item from queue");
-                    return None;
-                }
-            };
+            ret = Some(self.queue.items.get(i)?.to_string());
         }
 
         let ret_clone = ret.clone();
@@ -81,7 +57,7 @@ impl RecentFiles {
             self.add_recent(path)
         }
 
-        return ret;
+        ret
     }
 
     pub fn add_recent(&mut self, path: String) {
@@ -104,16 +80,14 @@ impl RecentFiles {
             self.queue.pop_back();
         }
 
-        // push to front
-        if let Err(e) = self.queue.push_front(path) {
-            eprintln!("{}", e);
-        }
+        // push to front, room was made above
+        let _ = self.queue.push_front(path);
     }
 }
 
 impl Navigate for RecentFiles {
     fn previous(&mut self) {
-        if self.queue.items.len() != 0 {
+        if !self.queue.items.is_empty() {
             let i = match self.state.selected() {
                 Some(i) => match i == 0 {
                     true => i,
@@ -126,7 +100,7 @@ impl Navigate for RecentFiles {
     }
 
     fn next(&mut self) {
-        if self.queue.items.len() != 0 {
+        if !self.queue.items.is_empty() {
             let i = match self.state.selected() {
                 Some(i) => match i >= self.queue.items.len() - 1 {
                     true => i,
@@ -0,0 +1,180 @@
+use super::doolist::DooItem;
+use super::state;
+use crate::error::{DooError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// the register used when none is picked, which every yank and delete also fills
+pub const UNNAMED: char = '"';
+
+/// tasks yanked or deleted, with their notes and subtasks, kept by name so they can be pasted
+/// into any list opened afterwards
+#[derive(Serialize, Deserialize, Default)]
+pub struct Registers {
+    named: BTreeMap<char, Vec<DooItem>>,
+    /// the register the next yank, delete or paste uses, picked with '"' and a name
+    #[serde(skip)]
+    chosen: Option<char>,
+    /// set by '"', the next key names the register
+    #[serde(skip)]
+    pub choosing: bool,
+}
+
+impl Registers {
+    /// load the registers at `path`, starting empty if there are none yet
+    pub fn load(path: &str) -> Result<Registers> {
+        Ok(state::load(path)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        state::save(path, self)
+    }
+
+    /// use the register `name` for the next yank, delete or paste. 'a' to 'z' name one, the
+    /// uppercase letter adds to it instead of replacing it
+    pub fn choose(&mut self, name: char) -> Result<()> {
+        self.choosing = false;
+        match name.is_ascii_alphabetic() || name == UNNAMED {
+            true => {
+                self.chosen = Some(name);
+                Ok(())
+            }
+            false => Err(DooError::Command(format!("not a register: '{}'", name))),
+        }
+    }
+
+    /// keep `items` in the chosen register, and in the unnamed one
+    pub fn store(&mut self, items: Vec<DooItem>) {
+        let name = self.chosen.take().unwrap_or(UNNAMED);
+        let register = self.named.entry(name.to_ascii_lowercase()).or_default();
+        match name.is_ascii_uppercase() {
+            true => register.extend(items),
+            false => *register = items,
+        }
+        let stored = register.clone();
+        self.named.insert(UNNAMED, stored);
+    }
+
+    /// the items in the chosen register, to paste
+    pub fn get(&mut self) -> Result<Vec<DooItem>> {
+        let name = self.chosen.take().unwrap_or(UNNAMED).to_ascii_lowercase();
+        match self.named.get(&name) {
+            Some(items) if !items.is_empty() => Ok(items.clone()),
+            _ => Err(DooError::Command(format!("register {} is empty", name))),
+        }
+    }
+
+    /// forget the register picked with '"', as when the key after it does not use one
+    pub fn cancel(&mut self) {
+        self.chosen = None;
+        self.choosing = false;
+    }
+
+    /// every register that holds something, with how many tasks it holds
+    pub fn summary(&self) -> String {
+        let filled: Vec<String> = self
+            .named
+            .iter()
+            .filter(|(_, items)| !items.is_empty())
+            .map(|(name, items)| format!("{} {}", name, items.len()))
+            .collect();
+        match filled.is_empty() {
+            true => String::from("every register is empty"),
+            false => format!("registers: {}", filled.join(", ")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn items(labels: &[&str]) -> Vec<DooItem> {
+        labels
+            .iter()
+            .map(|label| DooItem::new(label.to_string(), false))
+            .collect()
+    }
+
+    fn labels(items: Vec<DooItem>) -> Vec<String> {
+        items.into_iter().map(|item| item.label).collect()
+    }
+
+    #[test]
+    fn named_registers_replace_and_uppercase_appends() {
+        let mut registers = Registers::default();
+        registers.choose('a').unwrap();
+        registers.store(items(&["plan"]));
+        registers.choose('a').unwrap();
+        registers.store(items(&["ship"]));
+        registers.choose('A').unwrap();
+        registers.store(items(&["build"]));
+
+        registers.choose('a').unwrap();
+        assert_eq!(labels(registers.get().unwrap()), vec!["ship", "build"]);
+        // the unnamed register follows whatever was stored last, here all of 'a'
+        assert_eq!(labels(registers.get().unwrap()), vec!["ship", "build"]);
+        assert_eq!(registers.summary(), "registers: \" 2, a 2");
+    }
+
+    #[test]
+    fn a_choice_lasts_for_one_use() {
+        let mut registers = Registers::default();
+        registers.store(items(&["plan"]));
+        registers.choose('b').unwrap();
+        assert!(registers.get().is_err());
+        assert_eq!(labels(registers.get().unwrap()), vec!["plan"]);
+
+        registers.choose('b').unwrap();
+        registers.cancel();
+        assert!(!registers.choosing);
+        assert_eq!(labels(registers.get().unwrap()), vec!["plan"]);
+    }
+
+    #[test]
+    fn only_letters_and_the_unnamed_register_can_be_chosen() {
+        let mut registers = Registers {
+            choosing: true,
+            ..Default::default()
+        };
+        assert!(registers.choose('1').is_err());
+        assert!(!registers.choosing);
+        assert!(registers.choose('_').is_err());
+        assert!(registers.choose(UNNAMED).is_ok());
+        assert!(registers.get().is_err());
+        assert_eq!(registers.summary(), "every register is empty");
+    }
+
+    #[test]
+    fn registers_outlive_the_session() {
+        let dir = std::env::temp_dir().join(format!("doo-registers-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("registers.json").to_string_lossy().to_string();
+        assert_eq!(
+            Registers::load(&path).unwrap().summary(),
+            "every register is empty"
+        );
+
+        let mut registers = Registers::default();
+        registers.choose('a').unwrap();
+        let mut sketch = items(&["plan"]);
+        sketch[0].subtasks = items(&["sketch"]);
+        sketch[0].notes = String::from("call first");
+        registers.store(sketch);
+        // what was chosen but not used yet is not kept
+        registers.choose('b').unwrap();
+        registers.save(&path).unwrap();
+
+        let mut loaded = Registers::load(&path).unwrap();
+        let plan = loaded.get().unwrap().remove(0);
+        assert_eq!(plan.notes, "call first");
+        assert_eq!(plan.subtasks[0].label, "sketch");
+        loaded.choose('b').unwrap();
+        assert!(loaded.get().is_err());
+
+        fs::write(&path, "[").unwrap();
+        assert!(Registers::load(&path).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
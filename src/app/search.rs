@@ -0,0 +1,285 @@
+use super::doolist::{DooList, ItemPath};
+use crate::config::SearchConfig;
+use regex::{Regex, RegexBuilder};
+
+enum Matcher {
+    Literal(String),
+    Regex(Regex),
+    Invalid,
+}
+
+pub struct Search {
+    pub query: String,
+    /// visible row selected when search mode was entered, restored on <esc>
+    pub origin: Option<usize>,
+    regex: bool,
+    ignore_case: bool,
+    smart_case: bool,
+    matcher: Option<Matcher>,
+}
+
+impl Search {
+    pub fn new(config: &SearchConfig) -> Search {
+        Search {
+            query: String::new(),
+            origin: None,
+            regex: config.regex,
+            ignore_case: config.ignore_case,
+            smart_case: config.smart_case,
+            matcher: None,
+        }
+    }
+
+    pub fn set_query(&mut self, query: String) {
+        // smart case: an uppercase letter in the query turns case sensitivity back on
+        let ignore_case =
+            self.ignore_case && !(self.smart_case && query.chars().any(|c| c.is_uppercase()));
+
+        // a literal search ignoring case goes through an escaped regex too, since lowercasing
+        // the label would shift the byte ranges of the matches
+        self.matcher = match query.is_empty() {
+            true => None,
+            false if self.regex || ignore_case => Some(
+                RegexBuilder::new(&match self.regex {
+                    true => query.clone(),
+                    false => regex::escape(&query),
+                })
+                .case_insensitive(ignore_case)
+                .build()
+                .map_or(Matcher::Invalid, Matcher::Regex),
+            ),
+            false => Some(Matcher::Literal(query.clone())),
+        };
+        self.query = query;
+    }
+
+    pub fn clear(&mut self) {
+        self.set_query(String::new());
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.matcher.is_some()
+    }
+
+    pub fn is_invalid(&self) -> bool {
+        matches!(self.matcher, Some(Matcher::Invalid))
+    }
+
+    /// byte range of the first match in `label`, if any
+    pub fn find(&self, label: &str) -> Option<(usize, usize)> {
+        match self.matcher.as_ref()? {
+            Matcher::Literal(needle) => label
+                .find(needle.as_str())
+                .map(|start| (start, start + needle.len())),
+            Matcher::Regex(re) => re
+                .find(label)
+                .filter(|m| !m.is_empty())
+                .map(|m| (m.start(), m.end())),
+            Matcher::Invalid => None,
+        }
+    }
+
+    /// every matching item, in tree order, whether or not it is folded away
+    pub fn matches(&self, doolist: &DooList) -> Vec<ItemPath> {
+        let paths = doolist.filtered_paths();
+        self.positions(doolist, &paths)
+            .into_iter()
+            .map(|i| paths[i].clone())
+            .collect()
+    }
+
+    /// indices into `paths` of the items whose label matches
+    fn positions(&self, doolist: &DooList, paths: &[ItemPath]) -> Vec<usize> {
+        if !self.is_active() {
+            return Vec::new();
+        }
+
+        paths
+            .iter()
+            .enumerate()
+            .filter(|(_, path)| {
+                doolist
+                    .get(path)
+                    .is_some_and(|item| self.find(&item.label).is_some())
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// the next match after the selection, wrapping around the end of the list
+    pub fn next_match(&self, doolist: &DooList, reverse: bool) -> Option<ItemPath> {
+        let paths = doolist.filtered_paths();
+        let positions = self.positions(doolist, &paths);
+        let current = doolist
+            .selected_path()
+            .and_then(|s| paths.iter().position(|p| *p == s));
+
+        let found = match (reverse, current) {
+            (_, None) => positions.first(),
+            (false, Some(c)) => positions
+                .iter()
+                .find(|i| **i > c)
+                .or_else(|| positions.first()),
+            (true, Some(c)) => positions
+                .iter()
+                .rev()
+                .find(|i| **i < c)
+                .or_else(|| positions.last()),
+        };
+        found.map(|i| paths[*i].clone())
+    }
+
+    /// the first match at or after the visible row `from`, used while typing a query
+    pub fn first_match_from(&self, doolist: &DooList, from: usize) -> Option<ItemPath> {
+        let paths = doolist.filtered_paths();
+        let positions = self.positions(doolist, &paths);
+        let start = doolist
+            .visible()
+            .get(from)
+            .and_then(|v| paths.iter().position(|p| *p == v.path))
+            .unwrap_or(0);
+
+        positions
+            .iter()
+            .find(|i| **i >= start)
+            .or_else(|| positions.first())
+            .map(|i| paths[*i].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::doolist::DooItem;
+
+    fn search(regex: bool, ignore_case: bool, smart_case: bool, query: &str) -> Search {
+        let mut search = Search::new(&SearchConfig {
+            regex,
+            ignore_case,
+            smart_case,
+        });
+        search.set_query(String::from(query));
+        search
+    }
+
+    fn item(label: &str, subtasks: Vec<DooItem>) -> DooItem {
+        let mut item = DooItem::new(String::from(label), false);
+        item.subtasks = subtasks;
+        item
+    }
+
+    /// Plan > (design > sketch plan, build), ship plan
+    fn doolist() -> DooList {
+        let mut doolist = DooList::new();
+        doolist.list = vec![
+            item(
+                "Plan",
+                vec![
+                    item("design", vec![item("sketch plan", vec![])]),
+                    item("build", vec![]),
+                ],
+            ),
+            item("ship plan", vec![]),
+        ];
+        doolist.select_path(&[0]);
+        doolist
+    }
+
+    #[test]
+    fn smart_case_turns_on_with_an_uppercase_letter() {
+        assert_eq!(search(false, true, true, "plan").find("Plan"), Some((0, 4)));
+        assert_eq!(search(false, true, true, "Plan").find("plan"), None);
+        assert_eq!(
+            search(false, true, true, "Plan").find("a Plan"),
+            Some((2, 6))
+        );
+        // without smart case the query's case does not matter
+        assert_eq!(
+            search(false, true, false, "PLAN").find("plan"),
+            Some((0, 4))
+        );
+        // and without ignore case it always does
+        assert_eq!(search(false, false, true, "plan").find("Plan"), None);
+    }
+
+    #[test]
+    fn regex_queries_follow_the_same_case_rules() {
+        assert_eq!(
+            search(true, true, true, "p.an").find("a PLAN"),
+            Some((2, 6))
+        );
+        assert_eq!(search(true, true, true, "P.an").find("plan"), None);
+        assert_eq!(
+            search(true, false, false, r"\d+").find("in 42 days"),
+            Some((3, 5))
+        );
+        // a literal search takes the same text as it is
+        assert_eq!(search(false, true, true, "p.an").find("plan"), None);
+        // an empty match highlights nothing
+        assert_eq!(search(true, true, true, "x*").find("plan"), None);
+    }
+
+    #[test]
+    fn a_broken_regex_is_invalid_rather_than_an_error() {
+        let broken = search(true, true, true, "(plan");
+        assert!(broken.is_active() && broken.is_invalid());
+        assert_eq!(broken.find("(plan"), None);
+        assert!(broken.matches(&doolist()).is_empty());
+
+        let mut cleared = broken;
+        cleared.clear();
+        assert!(!cleared.is_active() && !cleared.is_invalid());
+    }
+
+    #[test]
+    fn ranges_point_into_the_original_label() {
+        // 'İ' lowercases to more bytes than it takes, which must not shift the range after it
+        assert_eq!(
+            search(false, true, true, "plan").find("İ plan"),
+            Some((3, 7))
+        );
+        assert_eq!(
+            search(false, true, true, "plan").find("İPLAN"),
+            Some((2, 6))
+        );
+        assert_eq!(
+            search(false, true, true, "牛奶").find("buy 牛奶"),
+            Some((4, 10))
+        );
+    }
+
+    #[test]
+    fn matches_wrap_around_and_include_folded_tasks() {
+        let mut doolist = doolist();
+        doolist.fold_selection();
+        let plan = search(false, true, true, "plan");
+        assert_eq!(
+            plan.matches(&doolist),
+            vec![vec![0], vec![0, 0, 0], vec![1]]
+        );
+
+        assert_eq!(plan.next_match(&doolist, false), Some(vec![0, 0, 0]));
+        assert_eq!(plan.next_match(&doolist, true), Some(vec![1]));
+        doolist.select_path(&[1]);
+        assert_eq!(plan.next_match(&doolist, false), Some(vec![0]));
+
+        let none = search(false, true, true, "zzz");
+        assert_eq!(none.next_match(&doolist, false), None);
+        assert_eq!(
+            Search::new(&SearchConfig::default()).matches(&doolist),
+            Vec::<ItemPath>::new()
+        );
+    }
+
+    #[test]
+    fn typing_finds_the_first_match_from_where_search_began() {
+        let doolist = doolist();
+        let build = search(false, true, true, "b");
+        assert_eq!(build.first_match_from(&doolist, 0), Some(vec![0, 1]));
+        let plan = search(false, true, true, "plan");
+        // row 3 is build, so the next plan is ship's
+        assert_eq!(plan.first_match_from(&doolist, 3), Some(vec![1]));
+        // past the end it wraps to the first
+        assert_eq!(plan.first_match_from(&doolist, 9), Some(vec![0]));
+    }
+}
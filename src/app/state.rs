@@ -0,0 +1,54 @@
+use super::backup;
+use crate::error::{DooError, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+
+/// read the json state file at `path`, such as the recent files or the registers. none when
+/// it does not exist yet, which is the case until doo first saves it
+pub fn load<T: DeserializeOwned>(path: &str) -> Result<Option<T>> {
+    if !Path::new(path).exists() {
+        return Ok(None);
+    }
+
+    let file = File::open(path).map_err(|e| DooError::io(path, e))?;
+    let reader = BufReader::new(file);
+    serde_json::from_reader(reader)
+        .map(Some)
+        .map_err(|e| DooError::serde(path, e))
+}
+
+/// write `state` to `path` in one go, creating its directory first
+pub fn save<T: Serialize>(path: &str, state: &T) -> Result<()> {
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir).map_err(|e| DooError::io(path, e))?;
+    }
+    backup::write_atomic(path, 0, |writer| {
+        serde_json::to_writer_pretty(writer, state).map_err(|e| DooError::serialize(path, e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saving_creates_the_directory_and_loads_back() {
+        let dir = std::env::temp_dir().join(format!("doo-state-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("nested").join("state.json");
+        let path = path.to_string_lossy();
+
+        assert!(load::<Vec<String>>(&path).unwrap().is_none());
+        save(&path, &vec![String::from("plan")]).unwrap();
+        assert_eq!(
+            load::<Vec<String>>(&path).unwrap(),
+            Some(vec![String::from("plan")])
+        );
+
+        std::fs::write(path.as_ref(), "not json").unwrap();
+        assert!(load::<Vec<String>>(&path).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,126 @@
+use super::Storage;
+use crate::app::backup;
+use crate::app::doolist::DooList;
+use crate::app::formats::{markdown, todotxt, Format};
+use crate::error::{DooError, Result};
+use std::fs::{self, File};
+use std::io::{BufReader, Write};
+use std::path::Path;
+
+/// lists kept one per file in any of the supported formats, written atomically with up to
+/// `backups` rotating copies of what was there before
+pub struct FileStorage {
+    format: Format,
+    backups: usize,
+}
+
+impl FileStorage {
+    pub fn new(format: Format, backups: usize) -> FileStorage {
+        FileStorage { format, backups }
+    }
+
+    fn write_text(&self, path: &str, text: &str) -> Result<()> {
+        backup::write_atomic(path, self.backups, |writer| {
+            writer
+                .write_all(text.as_bytes())
+                .map_err(|e| DooError::io(path, e))
+        })
+    }
+}
+
+impl Storage for FileStorage {
+    fn load(&self, path: &str) -> Result<DooList> {
+        let mut doolist = match self.format {
+            Format::Doo => {
+                let file = File::open(path).map_err(|e| DooError::io(path, e))?;
+                let reader = BufReader::new(file);
+                serde_json::from_reader(reader).map_err(|e| DooError::serde(path, e))?
+            }
+            Format::TodoTxt => {
+                let text = fs::read_to_string(path).map_err(|e| DooError::io(path, e))?;
+                let mut doolist = DooList::new();
+                doolist.list = todotxt::read(&text);
+                doolist
+            }
+            Format::Markdown => {
+                let text = fs::read_to_string(path).map_err(|e| DooError::io(path, e))?;
+                markdown::read(&text)
+            }
+        };
+        doolist.refresh_tags();
+        Ok(doolist)
+    }
+
+    fn save(&self, doolist: &DooList, path: &str) -> Result<()> {
+        match self.format {
+            Format::Doo => backup::write_atomic(path, self.backups, |writer| {
+                serde_json::to_writer_pretty(writer, doolist)
+                    .map_err(|e| DooError::serialize(path, e))
+            }),
+            Format::TodoTxt => self.write_text(path, &todotxt::write(doolist)),
+            Format::Markdown => {
+                // only the checklist is replaced, keeping the rest of the file
+                let existing = fs::read_to_string(path).ok();
+                self.write_text(path, &markdown::write(doolist, existing.as_deref()))
+            }
+        }
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        Path::new(path).is_file()
+    }
+
+    /// files in the same directory as `path` that are in this backend's format
+    fn lists(&self, path: &str) -> Result<Vec<String>> {
+        let dir = match Path::new(path) {
+            p if p.is_dir() => p,
+            p => p.parent().unwrap_or(Path::new(".")),
+        };
+        let entries = fs::read_dir(dir).map_err(|e| DooError::io(&dir.to_string_lossy(), e))?;
+
+        let mut lists: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|p| p.is_file())
+            .map(|p| p.to_string_lossy().to_string())
+            .filter(|p| match self.format {
+                // anything unrecognised reads as doo's own format, so only look at json
+                Format::Doo => p.ends_with(".json"),
+                format => Format::from_path(p) == format,
+            })
+            .collect();
+        lists.sort();
+        Ok(lists)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_keeps_backups_out_of_the_lists() {
+        let dir = std::env::temp_dir().join(format!("doo-file-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("list.json").to_string_lossy().to_string();
+        let storage = FileStorage::new(Format::Doo, 1);
+
+        let mut doolist = DooList::new();
+        for label in ["first", "second", "third"] {
+            doolist.add_from_label(String::from(label));
+            storage.save(&doolist, &path).unwrap();
+        }
+
+        assert_eq!(storage.load(&path).unwrap().list.len(), 3);
+        let backup = backup::backup_path(&path, 1);
+        assert_eq!(storage.load(&backup).unwrap().list.len(), 2);
+        assert!(!storage.exists(&backup::backup_path(&path, 2)));
+        assert_eq!(storage.lists(&path).unwrap(), vec![path.clone()]);
+        assert!(storage
+            .load(&dir.join("none.json").to_string_lossy())
+            .is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
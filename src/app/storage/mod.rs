@@ -0,0 +1,185 @@
+mod file;
+mod sqlite;
+
+pub use file::FileStorage;
+pub use sqlite::{SqliteStorage, SCHEME};
+
+use super::doolist::DooList;
+use super::formats::Format;
+use super::lists::Navigate;
+use crate::config::DooConfig;
+use crate::error::{DooError, Result};
+use crate::utils;
+use tui::widgets::ListState;
+
+/// somewhere lists are kept. a location names one list within the backend, e.g. a file path
+pub trait Storage {
+    fn load(&self, location: &str) -> Result<DooList>;
+
+    /// write `doolist` to `location`, creating it when it does not exist yet
+    fn save(&self, doolist: &DooList, location: &str) -> Result<()>;
+
+    fn exists(&self, location: &str) -> bool;
+
+    /// the lists this backend can find alongside `location`, e.g. the other files in its
+    /// directory
+    fn lists(&self, location: &str) -> Result<Vec<String>>;
+}
+
+/// the backend for the list at `location`. 'db://<name>' lists live in the configured
+/// database. files go by the storage setting, which with 'auto' picks from the extension the
+/// same way ':import' and 'doo convert' do: '.md' is a markdown checklist, '.txt' todo.txt and
+/// anything else doo's own json
+pub fn open(location: &str, config: &DooConfig) -> Box<dyn Storage> {
+    if location.starts_with(sqlite::SCHEME) {
+        return Box::new(SqliteStorage::new(config.database_path()));
+    }
+
+    // a setting that can not be read was reported at startup, so it counts as 'auto' here
+    let format = match configured_format(&config.storage) {
+        Ok(Some(format)) => format,
+        _ => Format::from_path(location),
+    };
+    with_format(format, config.backups)
+}
+
+/// the format the storage setting uses for every list, none when it is 'auto'
+pub fn configured_format(setting: &str) -> Result<Option<Format>> {
+    match setting {
+        "auto" => Ok(None),
+        name => Format::parse(name).map(Some).ok_or_else(|| {
+            DooError::Config(format!(
+                "storage must be auto, doo, md or todo.txt, not '{}'",
+                name
+            ))
+        }),
+    }
+}
+
+/// a file backend for an explicitly chosen format, as used by ':export' and 'doo convert'
+pub fn with_format(format: Format, backups: usize) -> Box<dyn Storage> {
+    Box::new(FileStorage::new(format, backups))
+}
+
+/// make a location typed by the user absolute, with '~' standing for the home directory.
+/// database locations are left as they are
+pub fn resolve(location: &str) -> String {
+    match location.starts_with(sqlite::SCHEME) {
+        true => location.to_string(),
+        false => utils::get_abs_path_from(utils::expand_home(location)),
+    }
+}
+
+/// the lists that can be opened from the ':lists' screen
+pub struct Lists {
+    pub items: Vec<String>,
+    pub state: ListState,
+}
+
+impl Default for Lists {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lists {
+    pub fn new() -> Lists {
+        Lists {
+            items: Vec::new(),
+            state: ListState::default(),
+        }
+    }
+
+    /// every list the backend for `location` knows of
+    pub fn find(location: &str, config: &DooConfig) -> Result<Lists> {
+        let mut found = Lists::new();
+        found.items = open(location, config).lists(location)?;
+        found.next();
+        Ok(found)
+    }
+
+    pub fn select(&self) -> Option<String> {
+        self.state
+            .selected()
+            .and_then(|i| self.items.get(i))
+            .cloned()
+    }
+}
+
+impl Navigate for Lists {
+    fn previous(&mut self) {
+        if !self.items.is_empty() {
+            let i = match self.state.selected() {
+                Some(i) => i.saturating_sub(1),
+                None => 0,
+            };
+            self.state.select(Some(i));
+        }
+    }
+
+    fn next(&mut self) {
+        if !self.items.is_empty() {
+            let i = match self.state.selected() {
+                Some(i) => (i + 1).min(self.items.len() - 1),
+                None => 0,
+            };
+            self.state.select(Some(i));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_setting_is_auto_or_a_format() {
+        assert_eq!(configured_format("auto").unwrap(), None);
+        assert_eq!(configured_format("md").unwrap(), Some(Format::Markdown));
+        assert_eq!(
+            configured_format("todo.txt").unwrap(),
+            Some(Format::TodoTxt)
+        );
+        assert!(matches!(
+            configured_format("yaml"),
+            Err(DooError::Config(message)) if message.contains("'yaml'")
+        ));
+    }
+
+    #[test]
+    fn auto_storage_follows_the_extension_like_import_does() {
+        let dir = std::env::temp_dir().join(format!("doo-storage-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut doolist = DooList::new();
+        doolist.add_from_label(String::from("plan"));
+
+        let mut config = DooConfig {
+            backups: 0,
+            ..Default::default()
+        };
+        let path = |file: &str| dir.join(file).to_string_lossy().to_string();
+        for file in ["list.txt", "list.md", "list.json"] {
+            open(&path(file), &config)
+                .save(&doolist, &path(file))
+                .unwrap();
+            let imported = with_format(Format::from_path(&path(file)), 0)
+                .load(&path(file))
+                .unwrap();
+            assert_eq!(imported.list[0].label, "plan");
+        }
+        let written = |file: &str| std::fs::read_to_string(path(file)).unwrap();
+        assert!(written("list.txt").contains("plan") && !written("list.txt").contains('{'));
+        assert!(written("list.md").contains("- [ ] plan"));
+        assert!(written("list.json").starts_with('{'));
+
+        // a format in the config wins over the extension
+        config.storage = String::from("doo");
+        open(&path("other.txt"), &config)
+            .save(&doolist, &path("other.txt"))
+            .unwrap();
+        assert!(written("other.txt").starts_with('{'));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
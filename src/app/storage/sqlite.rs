@@ -0,0 +1,501 @@
+use super::Storage;
+use crate::app::doolist::{DooItem, DooList};
+use crate::error::{DooError, Result};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// prefix of the locations that name a list in the database, as in 'db://work'
+pub const SCHEME: &str = "db://";
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS lists (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE,
+        title TEXT
+    );
+    CREATE TABLE IF NOT EXISTS items (
+        id INTEGER PRIMARY KEY,
+        list_id INTEGER NOT NULL REFERENCES lists(id) ON DELETE CASCADE,
+        parent_id INTEGER REFERENCES items(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        label TEXT NOT NULL,
+        complete INTEGER NOT NULL,
+        folded INTEGER NOT NULL,
+        notes TEXT NOT NULL,
+        due TEXT,
+        priority TEXT,
+        created TEXT,
+        completed TEXT
+    );
+    CREATE TABLE IF NOT EXISTS item_tags (
+        item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+        tag TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS items_by_parent ON items(list_id, parent_id, position);
+    CREATE INDEX IF NOT EXISTS items_by_completion ON items(list_id, complete);
+    CREATE INDEX IF NOT EXISTS item_tags_by_tag ON item_tags(tag);
+";
+
+/// many lists kept in one sqlite database, one row per list and per item. saving compares the
+/// list with its rows and only writes the ones that changed, inside a single transaction
+pub struct SqliteStorage {
+    path: String,
+}
+
+impl SqliteStorage {
+    pub fn new(path: String) -> SqliteStorage {
+        SqliteStorage { path }
+    }
+
+    /// the list name in a 'db://<name>' location
+    pub fn list_name(location: &str) -> Result<&str> {
+        match location.strip_prefix(SCHEME) {
+            Some(name) if !name.is_empty() => Ok(name),
+            _ => Err(DooError::Command(format!(
+                "not a list in the database: {}, use '{}<name>'",
+                location, SCHEME
+            ))),
+        }
+    }
+
+    fn connect(&self) -> Result<Connection> {
+        if let Some(dir) = Path::new(&self.path).parent() {
+            fs::create_dir_all(dir).map_err(|e| DooError::io(&self.path, e))?;
+        }
+        let connection = Connection::open(&self.path).map_err(|e| self.error(e))?;
+        connection
+            .execute_batch("PRAGMA foreign_keys = ON;")
+            .and_then(|_| connection.execute_batch(SCHEMA))
+            .map_err(|e| self.error(e))?;
+        Ok(connection)
+    }
+
+    fn error(&self, e: rusqlite::Error) -> DooError {
+        DooError::sqlite(&self.path, e)
+    }
+
+    fn list_id(connection: &Connection, name: &str) -> rusqlite::Result<Option<i64>> {
+        connection
+            .query_row("SELECT id FROM lists WHERE name = ?1", [name], |row| {
+                row.get(0)
+            })
+            .optional()
+    }
+
+    /// every item of a list with its row id, under the id of its parent and in order, without
+    /// their subtasks
+    fn read_items(connection: &Connection, list_id: i64) -> rusqlite::Result<Rows> {
+        let mut tags: HashMap<i64, BTreeSet<String>> = HashMap::new();
+        let mut statement = connection.prepare(
+            "SELECT item_tags.item_id, item_tags.tag FROM item_tags
+             JOIN items ON items.id = item_tags.item_id WHERE items.list_id = ?1",
+        )?;
+        for row in statement.query_map([list_id], |row| Ok((row.get(0)?, row.get(1)?)))? {
+            let (id, tag): (i64, String) = row?;
+            tags.entry(id).or_default().insert(tag);
+        }
+
+        let mut statement = connection.prepare(
+            "SELECT id, parent_id, label, complete, folded, notes, due, priority, created,
+                completed
+             FROM items WHERE list_id = ?1 ORDER BY position",
+        )?;
+        let rows = statement.query_map([list_id], |row| {
+            let mut item = DooItem::new(row.get(2)?, row.get(3)?);
+            item.folded = row.get(4)?;
+            item.notes = row.get(5)?;
+            item.due = row.get(6)?;
+            item.priority = row
+                .get::<_, Option<String>>(7)?
+                .and_then(|p| p.chars().next());
+            item.created = row.get(8)?;
+            item.completed = row.get(9)?;
+            Ok((row.get::<_, i64>(0)?, row.get::<_, Option<i64>>(1)?, item))
+        })?;
+
+        let mut children = Rows::new();
+        for row in rows {
+            let (id, parent_id, mut item) = row?;
+            item.tags = tags.remove(&id).unwrap_or_default();
+            children.entry(parent_id).or_default().push((id, item));
+        }
+        Ok(children)
+    }
+
+    /// bring the rows under `parent_id` in line with `items`. each item keeps the row of the
+    /// sibling with its label, or else of a leftover sibling, and only rows that differ are
+    /// written. rows no item took are deleted along with their subtasks
+    fn sync(
+        tx: &Transaction,
+        list_id: i64,
+        parent_id: Option<i64>,
+        items: &[DooItem],
+        rows: &mut Rows,
+    ) -> rusqlite::Result<()> {
+        let mut siblings: Vec<Option<(i64, usize, DooItem)>> = rows
+            .remove(&parent_id)
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .map(|(position, (id, item))| Some((id, position, item)))
+            .collect();
+        let mut pairs: Vec<Option<(i64, usize, DooItem)>> = items
+            .iter()
+            .map(|item| {
+                let i = siblings
+                    .iter()
+                    .position(|row| matches!(row, Some((_, _, old)) if old.label == item.label))?;
+                siblings[i].take()
+            })
+            .collect();
+        let mut leftover = siblings.into_iter().flatten();
+        for pair in pairs.iter_mut().filter(|pair| pair.is_none()) {
+            *pair = leftover.next();
+        }
+        for (id, _, _) in leftover {
+            tx.execute("DELETE FROM items WHERE id = ?1", [id])?;
+        }
+
+        for (position, (item, pair)) in items.iter().zip(pairs).enumerate() {
+            let id = match pair {
+                Some((id, old_position, old)) => {
+                    if old_position != position || !same_row(&old, item) {
+                        tx.execute(
+                            "UPDATE items SET position = ?2, label = ?3, complete = ?4,
+                                folded = ?5, notes = ?6, due = ?7, priority = ?8, created = ?9,
+                                completed = ?10
+                             WHERE id = ?1",
+                            params![
+                                id,
+                                position as i64,
+                                item.label,
+                                item.complete,
+                                item.folded,
+                                item.notes,
+                                item.due,
+                                item.priority.map(String::from),
+                                item.created,
+                                item.completed,
+                            ],
+                        )?;
+                    }
+                    if old.tags != item.tags {
+                        tx.execute("DELETE FROM item_tags WHERE item_id = ?1", [id])?;
+                        SqliteStorage::insert_tags(tx, id, item)?;
+                    }
+                    id
+                }
+                None => {
+                    tx.execute(
+                        "INSERT INTO items (list_id, parent_id, position, label, complete, folded,
+                            notes, due, priority, created, completed)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                        params![
+                            list_id,
+                            parent_id,
+                            position as i64,
+                            item.label,
+                            item.complete,
+                            item.folded,
+                            item.notes,
+                            item.due,
+                            item.priority.map(String::from),
+                            item.created,
+                            item.completed,
+                        ],
+                    )?;
+                    let id = tx.last_insert_rowid();
+                    SqliteStorage::insert_tags(tx, id, item)?;
+                    id
+                }
+            };
+            SqliteStorage::sync(tx, list_id, Some(id), &item.subtasks, rows)?;
+        }
+        Ok(())
+    }
+
+    fn insert_tags(tx: &Transaction, id: i64, item: &DooItem) -> rusqlite::Result<()> {
+        for tag in &item.tags {
+            tx.execute(
+                "INSERT INTO item_tags (item_id, tag) VALUES (?1, ?2)",
+                params![id, tag],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// the items of a list by the id of their parent, as read from the database
+type Rows = HashMap<Option<i64>, Vec<(i64, DooItem)>>;
+
+/// whether an item's own columns match, leaving its tags and subtasks aside
+fn same_row(a: &DooItem, b: &DooItem) -> bool {
+    a.label == b.label
+        && a.complete == b.complete
+        && a.folded == b.folded
+        && a.notes == b.notes
+        && a.due == b.due
+        && a.priority == b.priority
+        && a.created == b.created
+        && a.completed == b.completed
+}
+
+impl Storage for SqliteStorage {
+    fn load(&self, location: &str) -> Result<DooList> {
+        let name = SqliteStorage::list_name(location)?;
+        let connection = self.connect()?;
+
+        let (list_id, title): (i64, Option<String>) = connection
+            .query_row(
+                "SELECT id, title FROM lists WHERE name = ?1",
+                [name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| self.error(e))?
+            .ok_or_else(|| DooError::Command(format!("no list named {} in {}", name, self.path)))?;
+
+        let mut children =
+            SqliteStorage::read_items(&connection, list_id).map_err(|e| self.error(e))?;
+
+        // assemble the tree from the top down
+        fn assemble(parent: Option<i64>, children: &mut Rows) -> Vec<DooItem> {
+            let items = children.remove(&parent).unwrap_or_default();
+            items
+                .into_iter()
+                .map(|(id, mut item)| {
+                    item.subtasks = assemble(Some(id), children);
+                    item
+                })
+                .collect()
+        }
+
+        let mut doolist = DooList::new();
+        doolist.name = title;
+        doolist.list = assemble(None, &mut children);
+        Ok(doolist)
+    }
+
+    fn save(&self, doolist: &DooList, location: &str) -> Result<()> {
+        let name = SqliteStorage::list_name(location)?;
+        let mut connection = self.connect()?;
+
+        let tx = connection.transaction().map_err(|e| self.error(e))?;
+        (|| {
+            let list_id = match SqliteStorage::list_id(&tx, name)? {
+                Some(id) => {
+                    tx.execute(
+                        "UPDATE lists SET title = ?1 WHERE id = ?2 AND title IS NOT ?1",
+                        params![doolist.name, id],
+                    )?;
+                    id
+                }
+                None => {
+                    tx.execute(
+                        "INSERT INTO lists (name, title) VALUES (?1, ?2)",
+                        params![name, doolist.name],
+                    )?;
+                    tx.last_insert_rowid()
+                }
+            };
+            let mut rows = SqliteStorage::read_items(&tx, list_id)?;
+            SqliteStorage::sync(&tx, list_id, None, &doolist.list, &mut rows)
+        })()
+        .map_err(|e| self.error(e))?;
+        tx.commit().map_err(|e| self.error(e))
+    }
+
+    fn exists(&self, location: &str) -> bool {
+        match (SqliteStorage::list_name(location), self.connect()) {
+            (Ok(name), Ok(connection)) => {
+                matches!(SqliteStorage::list_id(&connection, name), Ok(Some(_)))
+            }
+            _ => false,
+        }
+    }
+
+    /// every list in the database
+    fn lists(&self, _location: &str) -> Result<Vec<String>> {
+        let connection = self.connect()?;
+        let mut statement = connection
+            .prepare("SELECT name FROM lists ORDER BY name")
+            .map_err(|e| self.error(e))?;
+        let names = statement
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| self.error(e))?;
+
+        names
+            .map(|name| {
+                name.map(|n| format!("{}{}", SCHEME, n))
+                    .map_err(|e| self.error(e))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn item(label: &str, subtasks: Vec<DooItem>) -> DooItem {
+        let mut item = DooItem::new(String::from(label), false);
+        item.subtasks = subtasks;
+        item
+    }
+
+    /// plan > (design > sketch, build), ship
+    fn work() -> DooList {
+        let mut doolist = DooList::new();
+        doolist.name = Some(String::from("work"));
+        doolist.list = vec![
+            item(
+                "plan",
+                vec![
+                    item("design", vec![item("sketch", vec![])]),
+                    item("build", vec![]),
+                ],
+            ),
+            item("ship #release", vec![]),
+        ];
+        doolist
+    }
+
+    fn scratch(name: &str) -> (PathBuf, SqliteStorage) {
+        let dir = std::env::temp_dir().join(format!("doo-db-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let storage = SqliteStorage::new(dir.join("lists.db").to_string_lossy().to_string());
+        (dir, storage)
+    }
+
+    fn same_list(a: &DooList, b: &DooList) -> bool {
+        serde_json::to_string(&a.list).unwrap() == serde_json::to_string(&b.list).unwrap()
+    }
+
+    /// count every row written to the items table from here on
+    fn count_writes(storage: &SqliteStorage) -> Connection {
+        let connection = storage.connect().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE writes (n INTEGER);
+                 CREATE TRIGGER item_inserts AFTER INSERT ON items BEGIN INSERT INTO writes VALUES (1); END;
+                 CREATE TRIGGER item_updates AFTER UPDATE ON items BEGIN INSERT INTO writes VALUES (1); END;
+                 CREATE TRIGGER item_deletes AFTER DELETE ON items BEGIN INSERT INTO writes VALUES (1); END;",
+            )
+            .unwrap();
+        connection
+    }
+
+    fn writes(connection: &Connection) -> i64 {
+        let n = connection
+            .query_row("SELECT count(*) FROM writes", [], |row| row.get(0))
+            .unwrap();
+        connection.execute("DELETE FROM writes", []).unwrap();
+        n
+    }
+
+    fn id_of(connection: &Connection, label: &str) -> Option<i64> {
+        connection
+            .query_row("SELECT id FROM items WHERE label = ?1", [label], |row| {
+                row.get(0)
+            })
+            .optional()
+            .unwrap()
+    }
+
+    #[test]
+    fn keeps_many_lists_in_one_database() {
+        let (dir, storage) = scratch("lists");
+        let mut work = work();
+        work.list[0].subtasks[1].notes = String::from("by friday");
+        work.list[0].subtasks[1].priority = Some('A');
+        storage.save(&work, "db://work").unwrap();
+        storage.save(&DooList::new(), "db://home").unwrap();
+        // saving again leaves the rows as they are rather than adding to them
+        storage.save(&work, "db://work").unwrap();
+
+        let loaded = storage.load("db://work").unwrap();
+        assert_eq!(loaded.name.as_deref(), Some("work"));
+        assert!(same_list(&loaded, &work));
+        assert!(storage.exists("db://home") && !storage.exists("db://play"));
+        assert_eq!(
+            storage.lists("db://").unwrap(),
+            vec!["db://home", "db://work"]
+        );
+        assert!(storage.load("db://play").is_err());
+        assert!(storage.load("db://").is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn saving_writes_only_the_rows_that_changed() {
+        let (dir, storage) = scratch("diff");
+        let mut work = work();
+        storage.save(&work, "db://work").unwrap();
+        let connection = count_writes(&storage);
+        let (plan, build) = (id_of(&connection, "plan"), id_of(&connection, "build"));
+
+        storage.save(&work, "db://work").unwrap();
+        assert_eq!(writes(&connection), 0);
+
+        work.list[1].mark();
+        storage.save(&work, "db://work").unwrap();
+        assert_eq!(writes(&connection), 1);
+
+        // the design row and its sketch go, build moves up into its place
+        work.list[0].subtasks.remove(0);
+        storage.save(&work, "db://work").unwrap();
+        assert_eq!(writes(&connection), 3);
+        assert_eq!(id_of(&connection, "sketch"), None);
+
+        // a relabeled task keeps its row, a new one is added after the rest
+        work.list[0].subtasks[0].change_label(String::from("build it"));
+        work.list.push(item("celebrate", vec![]));
+        storage.save(&work, "db://work").unwrap();
+        assert_eq!(writes(&connection), 2);
+        assert_eq!(id_of(&connection, "build it"), build);
+        assert_eq!(id_of(&connection, "plan"), plan);
+
+        // swapping two tasks moves them, they keep their rows
+        work.list.swap(0, 1);
+        storage.save(&work, "db://work").unwrap();
+        assert_eq!(writes(&connection), 2);
+        assert_eq!(id_of(&connection, "plan"), plan);
+
+        assert!(same_list(&storage.load("db://work").unwrap(), &work));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tags_are_read_back_from_their_table() {
+        let (dir, storage) = scratch("tags");
+        let mut work = work();
+        storage.save(&work, "db://work").unwrap();
+        assert_eq!(
+            storage.load("db://work").unwrap().list[1].tags,
+            work.list[1].tags
+        );
+
+        work.list[1].change_label(String::from("ship @office #v2"));
+        storage.save(&work, "db://work").unwrap();
+        let connection = storage.connect().unwrap();
+        let mut statement = connection
+            .prepare("SELECT tag FROM item_tags ORDER BY tag")
+            .unwrap();
+        let tags: Vec<String> = statement
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|tag| tag.unwrap())
+            .collect();
+        assert_eq!(tags, vec!["#v2", "@office"]);
+        assert_eq!(
+            storage.load("db://work").unwrap().list[1].tags,
+            work.list[1].tags
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,148 @@
+use super::doolist::DooList;
+use super::lists::Navigate;
+use std::collections::{BTreeMap, BTreeSet};
+use tui::widgets::ListState;
+
+/// whether `token` is a '#tag', an '@context' or a todo.txt '+project'. tokens with anything
+/// but letters, digits, '-' and '_' after the sigil are left alone, which keeps '@due(fri)'
+/// and plain '#' out
+pub fn is_tag(token: &str) -> bool {
+    let mut chars = token.chars();
+    matches!(chars.next(), Some('#') | Some('@') | Some('+'))
+        && token.len() > 1
+        && chars.all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// every tag and context in a label, lowercased so '#Work' and '#work' are the same tag
+pub fn parse(label: &str) -> BTreeSet<String> {
+    label
+        .split_whitespace()
+        .filter(|token| is_tag(token))
+        .map(|token| token.to_lowercase())
+        .collect()
+}
+
+/// the tags used in a list with how many items carry each, for the ':tags' screen
+pub struct TagList {
+    pub items: Vec<(String, usize)>,
+    pub state: ListState,
+}
+
+impl TagList {
+    pub fn new() -> TagList {
+        TagList {
+            items: Vec::new(),
+            state: ListState::default(),
+        }
+    }
+
+    /// count every tag in `doolist`, filtered out items included, sorted by name
+    pub fn find(doolist: &DooList) -> TagList {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for path in doolist.paths() {
+            if let Some(item) = doolist.get(&path) {
+                for tag in &item.tags {
+                    *counts.entry(tag.clone()).or_default() += 1;
+                }
+            }
+        }
+
+        let mut found = TagList::new();
+        found.items = counts.into_iter().collect();
+        found.next();
+        found
+    }
+
+    pub fn select(&self) -> Option<String> {
+        self.state
+            .selected()
+            .and_then(|i| self.items.get(i))
+            .map(|(tag, _)| tag.clone())
+    }
+}
+
+impl Navigate for TagList {
+    fn previous(&mut self) {
+        if !self.items.is_empty() {
+            let i = match self.state.selected() {
+                Some(i) => i.saturating_sub(1),
+                None => 0,
+            };
+            self.state.select(Some(i));
+        }
+    }
+
+    fn next(&mut self) {
+        if !self.items.is_empty() {
+            let i = match self.state.selected() {
+                Some(i) => (i + 1).min(self.items.len() - 1),
+                None => 0,
+            };
+            self.state.select(Some(i));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::doolist::DooItem;
+
+    fn item(label: &str, subtasks: Vec<DooItem>) -> DooItem {
+        let mut item = DooItem::new(String::from(label), false);
+        item.subtasks = subtasks;
+        item
+    }
+
+    #[test]
+    fn tags_contexts_and_projects_are_tags() {
+        for token in ["#work", "@home", "+garden", "#q3-plan", "@at_desk", "#日本"] {
+            assert!(is_tag(token), "{}", token);
+        }
+        for token in ["#", "@", "+", "work", "@due(fri)", "#a.b", "1+1", "a#b"] {
+            assert!(!is_tag(token), "{}", token);
+        }
+    }
+
+    #[test]
+    fn parse_folds_case_and_skips_due_tokens() {
+        let tags = parse("pay rent #Money @Home +House @due(fri) #money");
+        let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+        assert_eq!(tags, vec!["#money", "+house", "@home"]);
+        assert!(parse("nothing to see @due(tomorrow 5pm)").is_empty());
+    }
+
+    #[test]
+    fn tag_list_counts_every_item_folded_or_not() {
+        let mut doolist = DooList::new();
+        doolist.list = vec![
+            item(
+                "plan #Work",
+                vec![item("design #work @desk", vec![]), item("build", vec![])],
+            ),
+            item("ship +launch #work", vec![]),
+        ];
+        doolist.select_path(&[0]);
+        doolist.fold_selection();
+
+        let tags = TagList::find(&doolist);
+        assert_eq!(
+            tags.items,
+            vec![
+                (String::from("#work"), 3),
+                (String::from("+launch"), 1),
+                (String::from("@desk"), 1),
+            ]
+        );
+        // the first tag is selected, and the selection stops at either end
+        assert_eq!(tags.select().as_deref(), Some("#work"));
+        let mut tags = tags;
+        tags.previous();
+        assert_eq!(tags.select().as_deref(), Some("#work"));
+        for _ in 0..5 {
+            tags.next();
+        }
+        assert_eq!(tags.select().as_deref(), Some("@desk"));
+        assert!(TagList::find(&DooList::new()).select().is_none());
+    }
+}
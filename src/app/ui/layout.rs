@@ -1,6 +1,8 @@
-use tui::layout::Constraint;
+use crate::config::{self, SizeSetting};
+use crate::error::{DooError, Result};
+use tui::layout::Rect;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum LayoutHorizontal {
     Full,
     Right,
@@ -9,51 +11,35 @@ pub enum LayoutHorizontal {
 }
 
 impl LayoutHorizontal {
-    pub fn constraints(&self) -> [Constraint; 3] {
+    /// the width used when none is configured
+    fn default_size(&self) -> Size {
         match self {
-            LayoutHorizontal::Full => [
-                Constraint::Percentage(100),
-                Constraint::Percentage(0),
-                Constraint::Percentage(0),
-            ],
-            LayoutHorizontal::Right => [
-                Constraint::Percentage(60),
-                Constraint::Percentage(40),
-                Constraint::Percentage(0),
-            ],
-            LayoutHorizontal::Center => [
-                Constraint::Percentage(30),
-                Constraint::Percentage(40),
-                Constraint::Percentage(0),
-            ],
-            LayoutHorizontal::Left => [
-                Constraint::Percentage(40),
-                Constraint::Percentage(60),
-                Constraint::Percentage(0),
-            ],
+            LayoutHorizontal::Full => Size::Percent(100),
+            _ => Size::Percent(40),
         }
     }
 
-    pub fn block_index(&self) -> usize {
+    /// how far from the left the window starts, given the columns it leaves free
+    fn offset(&self, free: u16) -> u16 {
         match self {
-            LayoutHorizontal::Full => 0,
-            LayoutHorizontal::Right => 1,
-            LayoutHorizontal::Center => 1,
-            LayoutHorizontal::Left => 0,
+            LayoutHorizontal::Full | LayoutHorizontal::Left => 0,
+            LayoutHorizontal::Center => free / 2,
+            LayoutHorizontal::Right => free,
         }
     }
 
-    pub fn from_str(str: &str) -> Self {
-        match str {
-            "full" => LayoutHorizontal::Full,
-            "right" => LayoutHorizontal::Right,
-            "left" => LayoutHorizontal::Left,
-            _ => LayoutHorizontal::Center,
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "full" => Some(LayoutHorizontal::Full),
+            "right" => Some(LayoutHorizontal::Right),
+            "center" => Some(LayoutHorizontal::Center),
+            "left" => Some(LayoutHorizontal::Left),
+            _ => None,
         }
     }
 }
 
-#[derive(Debug)] 
+#[derive(Debug, PartialEq)]
 pub enum LayoutVertical {
     Full,
     Top,
@@ -62,47 +48,324 @@ pub enum LayoutVertical {
 }
 
 impl LayoutVertical {
-    pub fn constraints(&self) -> [Constraint; 3] {
+    /// the height used when none is configured
+    fn default_size(&self) -> Size {
         match self {
-            LayoutVertical::Full => [
-                Constraint::Percentage(100),
-                Constraint::Percentage(0),
-                Constraint::Percentage(0),
-            ],
-            LayoutVertical::Top => [
-                Constraint::Percentage(60),
-                Constraint::Percentage(40),
-                Constraint::Percentage(0),
-            ],
-            LayoutVertical::Center => [
-                Constraint::Percentage(20),
-                Constraint::Percentage(60),
-                Constraint::Percentage(20),
-            ],
-            LayoutVertical::Bottom => [
-                Constraint::Percentage(40),
-                Constraint::Percentage(60),
-                Constraint::Percentage(0),
-            ],
+            LayoutVertical::Full => Size::Percent(100),
+            _ => Size::Percent(60),
         }
     }
 
-    pub fn block_index(&self) -> usize {
+    /// how far from the top the window starts, given the rows it leaves free
+    fn offset(&self, free: u16) -> u16 {
         match self {
-            LayoutVertical::Full => 0,
-            LayoutVertical::Top => 0,
-            LayoutVertical::Center => 1,
-            LayoutVertical::Bottom => 1,
+            LayoutVertical::Full | LayoutVertical::Top => 0,
+            LayoutVertical::Center => free / 2,
+            LayoutVertical::Bottom => free,
         }
     }
 
-    pub fn from_str(str: &str) -> Self {
-        match str {
-            "full" => LayoutVertical::Full,
-            "top" => LayoutVertical::Top,
-            "bottom" => LayoutVertical::Bottom,
-            _ => LayoutVertical::Center,
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "full" => Some(LayoutVertical::Full),
+            "top" => Some(LayoutVertical::Top),
+            "center" => Some(LayoutVertical::Center),
+            "bottom" => Some(LayoutVertical::Bottom),
+            _ => None,
         }
     }
 }
 
+/// where the window sits as one word: an edge such as 'top', a corner such as 'top-right', or
+/// 'center'
+fn parse_anchor(name: &str) -> Option<(LayoutVertical, LayoutHorizontal)> {
+    let (vertical, horizontal) = match name.split_once('-') {
+        Some((vertical, horizontal)) => (vertical, horizontal),
+        None => match name {
+            "left" | "right" => ("center", name),
+            _ => (name, "center"),
+        },
+    };
+    match (vertical, horizontal) {
+        ("top" | "center" | "bottom", "left" | "center" | "right") => Some((
+            LayoutVertical::parse(vertical)?,
+            LayoutHorizontal::parse(horizontal)?,
+        )),
+        _ => None,
+    }
+}
+
+/// a width or height, in terminal cells or as a share of the terminal
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Size {
+    Cells(u16),
+    Percent(u16),
+}
+
+impl Size {
+    /// a size as written in the config: a number of cells such as 80, or a share such as '50%'
+    pub fn parse(setting: &SizeSetting) -> Option<Size> {
+        let size = match setting {
+            SizeSetting::Cells(cells) => Size::Cells(*cells),
+            SizeSetting::Text(text) => match text.trim().strip_suffix('%') {
+                Some(percent) => Size::Percent(percent.trim().parse().ok()?),
+                None => Size::Cells(text.trim().parse().ok()?),
+            },
+        };
+        match size {
+            Size::Cells(0) | Size::Percent(0) => None,
+            Size::Percent(percent) if percent > 100 => None,
+            size => Some(size),
+        }
+    }
+
+    fn cells(&self, available: u16) -> u16 {
+        match self {
+            Size::Cells(cells) => *cells,
+            Size::Percent(percent) => (available as u32 * *percent as u32 / 100) as u16,
+        }
+    }
+}
+
+/// the size of the window along one side, kept within the bounds and the terminal
+struct Extent {
+    size: Option<Size>,
+    min: u16,
+    max: u16,
+}
+
+impl Extent {
+    fn cells(&self, default: Size, available: u16) -> u16 {
+        self.size
+            .unwrap_or(default)
+            .cells(available)
+            .clamp(self.min, self.max)
+            .min(available)
+    }
+}
+
+/// where on the terminal doo draws itself, and how big, from the 'layout' config section
+pub struct Window {
+    vertical: LayoutVertical,
+    horizontal: LayoutHorizontal,
+    width: Extent,
+    height: Extent,
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Window::new(&config::Layout::default()).expect("the default layout is valid")
+    }
+}
+
+impl Window {
+    /// the window the config asks for. every value that can not be read is reported in one
+    /// error
+    pub fn new(layout: &config::Layout) -> Result<Window> {
+        let mut problems = Vec::new();
+
+        let (vertical, horizontal) = match &layout.anchor {
+            Some(anchor) => parse_anchor(anchor).unwrap_or_else(|| {
+                problems.push(format!(
+                    "not an anchor: '{}', use an edge or corner such as 'top' or 'top-right', or 'center'",
+                    anchor
+                ));
+                (LayoutVertical::Center, LayoutHorizontal::Center)
+            }),
+            None => (
+                LayoutVertical::parse(&layout.vertical).unwrap_or_else(|| {
+                    problems.push(format!(
+                        "vertical must be full, top, center or bottom, not '{}'",
+                        layout.vertical
+                    ));
+                    LayoutVertical::Center
+                }),
+                LayoutHorizontal::parse(&layout.horizontal).unwrap_or_else(|| {
+                    problems.push(format!(
+                        "horizontal must be full, left, center or right, not '{}'",
+                        layout.horizontal
+                    ));
+                    LayoutHorizontal::Center
+                }),
+            ),
+        };
+
+        let mut extent =
+            |name: &str, size: &Option<SizeSetting>, min: Option<u16>, max: Option<u16>| {
+                let size = size.as_ref().and_then(|setting| {
+                    let size = Size::parse(setting);
+                    if size.is_none() {
+                        problems.push(format!(
+                            "{} must be a number of cells or a percentage up to 100%, not '{}'",
+                            name, setting
+                        ));
+                    }
+                    size
+                });
+                let (min, max) = (min.unwrap_or(0), max.unwrap_or(u16::MAX));
+                if min > max {
+                    problems.push(format!("min_{} is larger than max_{}", name, name));
+                }
+                Extent {
+                    size,
+                    min,
+                    max: max.max(min),
+                }
+            };
+        let width = extent("width", &layout.width, layout.min_width, layout.max_width);
+        let height = extent(
+            "height",
+            &layout.height,
+            layout.min_height,
+            layout.max_height,
+        );
+
+        match problems.is_empty() {
+            true => Ok(Window {
+                vertical,
+                horizontal,
+                width,
+                height,
+            }),
+            false => Err(DooError::Config(problems.join(", "))),
+        }
+    }
+
+    /// the part of `screen` the window takes up
+    pub fn area(&self, screen: Rect) -> Rect {
+        let width = self
+            .width
+            .cells(self.horizontal.default_size(), screen.width);
+        let height = self
+            .height
+            .cells(self.vertical.default_size(), screen.height);
+        Rect {
+            x: screen.x + self.horizontal.offset(screen.width - width),
+            y: screen.y + self.vertical.offset(screen.height - height),
+            width,
+            height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: 200,
+        height: 50,
+    };
+
+    fn cells(n: u16) -> Option<SizeSetting> {
+        Some(SizeSetting::Cells(n))
+    }
+
+    fn text(size: &str) -> Option<SizeSetting> {
+        Some(SizeSetting::Text(String::from(size)))
+    }
+
+    fn anchored(anchor: &str) -> config::Layout {
+        config::Layout {
+            anchor: Some(String::from(anchor)),
+            ..Default::default()
+        }
+    }
+
+    fn area(layout: config::Layout) -> Rect {
+        Window::new(&layout).unwrap().area(SCREEN)
+    }
+
+    fn problems(layout: config::Layout) -> String {
+        Window::new(&layout).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn the_old_presets_keep_their_place() {
+        let layout = |vertical: &str, horizontal: &str| config::Layout {
+            vertical: String::from(vertical),
+            horizontal: String::from(horizontal),
+            ..Default::default()
+        };
+        assert_eq!(area(layout("center", "center")), Rect::new(60, 10, 80, 30));
+        assert_eq!(area(layout("full", "full")), SCREEN);
+        assert_eq!(area(layout("bottom", "left")), Rect::new(0, 20, 80, 30));
+        assert_eq!(Window::default().area(SCREEN), area(Default::default()));
+    }
+
+    #[test]
+    fn anchors_and_sizes_place_the_window() {
+        assert_eq!(
+            area(config::Layout {
+                width: cells(60),
+                height: text("50%"),
+                ..anchored("top-right")
+            }),
+            Rect::new(140, 0, 60, 25)
+        );
+        assert_eq!(area(anchored("left")), Rect::new(0, 10, 80, 30));
+        assert_eq!(area(anchored("bottom")), Rect::new(60, 20, 80, 30));
+        // the anchor wins over vertical and horizontal
+        assert_eq!(
+            area(config::Layout {
+                vertical: String::from("top"),
+                ..anchored("center")
+            }),
+            Rect::new(60, 10, 80, 30)
+        );
+    }
+
+    #[test]
+    fn bounds_and_the_terminal_limit_the_size() {
+        assert_eq!(
+            area(config::Layout {
+                width: text("10%"),
+                min_width: Some(30),
+                height: text(" 40 "),
+                max_height: Some(20),
+                ..anchored("bottom-left")
+            }),
+            Rect::new(0, 30, 30, 20)
+        );
+        assert_eq!(
+            area(config::Layout {
+                width: cells(300),
+                ..anchored("right")
+            }),
+            Rect::new(0, 10, 200, 30)
+        );
+        // a tiny terminal still gets a window that fits
+        let tiny = Window::new(&config::Layout {
+            min_height: Some(10),
+            ..anchored("center")
+        })
+        .unwrap()
+        .area(Rect::new(0, 0, 10, 4));
+        assert_eq!(tiny, Rect::new(3, 0, 4, 4));
+    }
+
+    #[test]
+    fn every_bad_value_is_reported_at_once() {
+        let error = problems(config::Layout {
+            vertical: String::from("middle"),
+            horizontal: String::from("wide"),
+            width: text("150%"),
+            height: cells(0),
+            min_height: Some(30),
+            max_height: Some(10),
+            ..Default::default()
+        });
+        for part in ["'middle'", "'wide'", "'150%'", "not '0'", "min_height"] {
+            assert!(error.contains(part), "{} missing from {}", part, error);
+        }
+        assert!(problems(anchored("top-middle")).contains("'top-middle'"));
+        assert!(problems(anchored("left-top")).contains("'left-top'"));
+        assert!(problems(config::Layout {
+            width: text("wide"),
+            ..Default::default()
+        })
+        .contains("'wide'"));
+    }
+}
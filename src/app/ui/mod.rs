@@ -1,5 +1,7 @@
-mod layout;
+pub mod layout;
+pub mod theme;
 
+use chrono::Local;
 use tui::backend::Backend;
 use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use tui::style::{Color, Modifier, Style};
@@ -8,34 +10,17 @@ use tui::widgets::{
     Block, BorderType, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Wrap,
 };
 use tui::Frame;
+use unicode_width::UnicodeWidthStr;
 
-use super::{recent_files::RecentFiles, DooList, Screen};
-use layout::*;
+use super::{
+    backup::Backups, completion::Completion, doolist::ItemPath, due, line_editor::LineEditor,
+    notes::NotesEditor, recent_files::RecentFiles, search::Search, storage::Lists, tags::TagList,
+    DooList, Message, Screen, Severity,
+};
+use theme::Theme;
 
 use crate::app;
 
-// TODO: should support height, width parameter
-/// get the block
-fn get_doo_block_from_screen<B: Backend>(
-    f: &Frame<B>,
-    vertical_orientation: LayoutVertical,
-    horizontal_orientation: LayoutHorizontal,
-) -> Rect {
-    // cut the block into horizontal chunk, return new block to cut
-    let horizontal_cut = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(horizontal_orientation.constraints())
-        .split(f.size())[horizontal_orientation.block_index()];
-
-    // cut horizontal block vertically, return new block to use
-    let vertical_cut = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(vertical_orientation.constraints())
-        .split(horizontal_cut)[vertical_orientation.block_index()];
-
-    return vertical_cut;
-}
-
 fn get_doo_module_chunks(doo_module: Rect) -> Vec<Rect> {
     let doo_modules = Layout::default()
         .direction(Direction::Vertical)
@@ -43,17 +28,12 @@ fn get_doo_module_chunks(doo_module: Rect) -> Vec<Rect> {
         .constraints([Constraint::Percentage(90), Constraint::Percentage(10)].as_ref())
         .split(doo_module);
 
-    return doo_modules;
+    doo_modules
 }
 
 pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut app::App) {
-
     // working app area
-    let doo_module = get_doo_block_from_screen(
-        f,
-        LayoutVertical::from_str(&app.config.layout.vertical),
-        LayoutHorizontal::from_str(&app.config.layout.horizontal),
-    );
+    let doo_module = app.window.area(f.size());
 
     let doo_module_chunks = get_doo_module_chunks(doo_module);
 
@@ -61,7 +41,8 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut app::App) {
     let wrapper_block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(app.theme.fg(app.theme.border))
+        .style(Style::default().bg(app.theme.background));
 
     f.render_widget(wrapper_block, doo_module_chunks[0]);
 
@@ -73,52 +54,131 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut app::App) {
         .split(doo_module_chunks[0]);
 
     // render components
-    render_status_bar(
-        f,
-        &app.screen,
-        app.doolist.name.clone(),
-        &app.doolist.state,
-        app.doolist.list.len(),
-        core_module[0],
-    );
+    let theme = &app.theme;
+    render_status_bar(f, &app.screen, &app.doolist, theme, core_module[0]);
 
     match app.screen {
-        Screen::Help => render_help(f, core_module[1]),
-        Screen::DooList => render_doolist(f, &mut app.doolist, core_module[1]),
-        Screen::Recents => {
-            render_recents(f, &mut app.recent_files, &app.current_path, core_module[1])
+        Screen::Help => render_help(f, theme, core_module[1]),
+        Screen::DooList => {
+            let picked = match &app.visual {
+                Some(selection) => selection.paths(&app.doolist),
+                None => Vec::new(),
+            };
+            render_doolist(
+                f,
+                &mut app.doolist,
+                &app.search,
+                &picked,
+                theme,
+                core_module[1],
+            )
+        }
+        Screen::Recents => render_recents(
+            f,
+            &mut app.recent_files,
+            &app.current_path,
+            theme,
+            core_module[1],
+        ),
+        Screen::Backups => render_backups(f, &mut app.backups, theme, core_module[1]),
+        Screen::Tags => render_tags(f, &mut app.tags, theme, core_module[1]),
+        Screen::Lists => render_lists(f, &mut app.lists, &app.current_path, theme, core_module[1]),
+        Screen::Conflict => render_conflict(f, &app.current_path, theme, core_module[1]),
+        Screen::Notes => {
+            if let Some(editor) = &app.notes {
+                render_notes(f, editor, theme, core_module[1])
+            }
         }
     }
 
-    render_input_bar(f, &app.mode, app.input.clone(), doo_module_chunks[1]);
+    let search_info = match (&app.visual, app.search.is_invalid()) {
+        (Some(selection), _) => Some(format!(" {} picked ", selection.paths(&app.doolist).len())),
+        (None, true) => Some(String::from(" invalid pattern ")),
+        (None, false) if app.search.is_active() => Some(format!(
+            " {} matches ",
+            app.search.matches(&app.doolist).len()
+        )),
+        (None, false) => None,
+    };
+
+    render_input_bar(f, app, search_info, doo_module_chunks[1]);
 }
 
-fn render_doolist<B: Backend>(f: &mut Frame<B>, doolist: &mut DooList, chunk: Rect) {
+fn render_doolist<B: Backend>(
+    f: &mut Frame<B>,
+    doolist: &mut DooList,
+    search: &Search,
+    picked: &[ItemPath],
+    theme: &Theme,
+    chunk: Rect,
+) {
+    let now = Local::now().naive_local();
     let items: Vec<ListItem> = doolist
-        .list
+        .visible()
         .iter()
-        .map(|s| {
+        .filter_map(|v| doolist.get(&v.path).map(|s| (v, s)))
+        .map(|(v, s)| {
+            let depth = v.depth;
+            let fold_marker = match (s.has_subtasks(), s.folded) {
+                (false, _) => "  ",
+                (true, true) => "▸ ",
+                (true, false) => "▾ ",
+            };
+
             let completion_marker = match s.complete {
                 true => "[X] ",
                 false => "[ ] ",
             };
 
-            let mut item = vec![Span::styled(
-                completion_marker,
-                Style::default().fg(Color::White),
-            )];
-
-            item.push(match s.complete {
-                true => Span::styled(
-                    &s.label,
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::CROSSED_OUT),
-                ),
-                false => Span::styled(&s.label, Style::default().fg(Color::White)),
-            });
-
-            return ListItem::new(Spans::from(item));
+            let mut item = vec![
+                Span::raw("  ".repeat(depth)),
+                Span::styled(fold_marker, theme.fg(theme.dim)),
+                Span::styled(completion_marker, theme.fg(theme.text)),
+                priority_marker(s.priority, theme),
+            ];
+
+            let due_color = match (s.complete, s.due) {
+                (false, Some(due)) if due < now => Some(theme.overdue),
+                (false, Some(due)) if due.date() == now.date() => Some(theme.due_today),
+                _ => None,
+            };
+
+            let label_style = match s.complete {
+                true => theme.fg(theme.done).add_modifier(Modifier::CROSSED_OUT),
+                false => theme.fg(due_color.unwrap_or(theme.text)),
+            };
+
+            match search.find(&s.label) {
+                Some((start, end)) => {
+                    let match_style = label_style
+                        .bg(theme.search_match)
+                        .fg(theme.search_match_text);
+                    item.push(Span::styled(s.label[..start].to_string(), label_style));
+                    item.push(Span::styled(s.label[start..end].to_string(), match_style));
+                    item.push(Span::styled(s.label[end..].to_string(), label_style));
+                }
+                None => item.push(Span::styled(s.label.clone(), label_style)),
+            }
+
+            if s.has_notes() {
+                item.push(Span::styled(" ✎", theme.fg(theme.dim)));
+            }
+
+            if let Some(due) = s.due {
+                // right align the due date, keeping at least one space after the label
+                let relative = due::relative(&due, now);
+                let used = Spans::from(item.clone()).width() + relative.chars().count();
+                let padding = (chunk.width as usize).saturating_sub(used).max(1);
+                let due_style = theme.fg(due_color.unwrap_or(theme.dim));
+                item.push(Span::raw(" ".repeat(padding)));
+                item.push(Span::styled(relative, due_style));
+            }
+
+            let row_style = match picked.contains(&v.path) {
+                true => theme.visual_style(),
+                false => Style::default(),
+            };
+            ListItem::new(Spans::from(item)).style(row_style)
         })
         .collect();
 
@@ -130,39 +190,107 @@ fn render_doolist<B: Backend>(f: &mut Frame<B>, doolist: &mut DooList, chunk: Re
         )
         .style(Style::default())
         .start_corner(tui::layout::Corner::TopRight)
-        .highlight_style(
-            Style::default()
-                .add_modifier(Modifier::REVERSED)
-                .fg(Color::Cyan),
-        );
+        .highlight_style(theme.highlight_style());
 
     f.render_stateful_widget(live_draw_list, chunk, &mut doolist.state);
 }
 
-fn render_help<B: Backend>(f: &mut Frame<B>, chunk: Rect) {
-    let help_text = "doo has 4 modes: select, search, insert, and command.\n
+/// a fixed width priority column, so labels line up whether or not items have one
+fn priority_marker(priority: Option<char>, theme: &Theme) -> Span<'static> {
+    match priority {
+        None => Span::raw("    "),
+        Some(p) => {
+            let color = match p {
+                'A' => theme.priority_a,
+                'B' => theme.priority_b,
+                'C' => theme.priority_c,
+                _ => theme.priority_other,
+            };
+            Span::styled(format!("({}) ", p), theme.fg(color))
+        }
+    }
+}
+
+fn render_help<B: Backend>(f: &mut Frame<B>, theme: &Theme, chunk: Rect) {
+    let help_text = "doo has 5 modes: select, visual, search, insert, and command.\n
         In command mode, you can use the following commands:\n
-        \t:q -- quit
+        \t:q -- quit (:q! discards unsaved changes, as do :e!, :new!, :recent! and :lists!)
         \t:w | :saveas <optional filepath> -- save file (to path)
         \t:wq -- save and quit
         \t:e | :load <optional filepath> -- load file into doo
         \t:rename -- change the file display name
         \t:recent -- load a recent todo
+        \t:backups -- restore one of the backups kept on save
+        \t:lists <optional dir or db://> -- open another list next to this one or in the database
+        \t:priority <A-Z|high|med|low|none> -- set the priority of a task
+        \t:tag <#tag @context ..> -- add tags to a task
+        \t:sort <priority|due|done|label> -- sort the list, keeping subtasks under their parent
+        \t:filter <#tag @context ..> -- only show tasks with every given tag (no tags shows all)
+        \t:tags -- list every tag with how many tasks use it
+        \t:registers -- show how many tasks each register holds
+        \t:export <todo.txt|md|doo> <path> -- write the list in another format
+        \t:import <path> -- append the tasks of a todo.txt (.txt), markdown (.md) or doo file
+        \t:due <when> -- set the due date of a task, e.g. :due fri 5pm (no date clears it)
         \t:help -- open this menu
+        Up and down recall earlier commands, tab completes command names and file paths.
+        While typing, ctrl-w deletes a word, ctrl-u the line before the cursor,
+        and alt-b and alt-f move by words.\n
+        In select mode, s adds a subtask, h folds and l unfolds.
+        / searches the list as you type, n and N jump between matches.
+        u undoes the last edit and ctrl-r redoes it.
+        <enter> opens the notes of a task, <esc> saves and closes them,
+        and ctrl-c closes them without saving.
+        J and K move a task down and up, gg and G move it to the top and bottom,
+        and :m <n> moves it to position n among its siblings.
+        + and - raise and lower the priority of a task.
+        yy yanks a task, d deletes it, and p and P paste them below and above.
+        \"a before them uses register a, which stays when another list is opened.
+        V starts picking a range of tasks and <space> picks or drops single ones.
+        x, d, y, J, K, + and - then act on every picked task, as do :priority, :due
+        and :tag. V starts another range and <esc> stops picking.
+        These are the default keys, the keys section of the config can change them.
+        Typing @due(tomorrow) in a task name also sets its due date.
+        Overdue tasks are red and tasks due today are yellow.
+        When another program changes the open file, doo reloads it, or asks
+        whether to reload, keep or merge when there are unsaved changes.
         ";
 
     let help_paragraph = Paragraph::new(help_text)
-        .style(Style::default())
+        .style(theme.fg(theme.text))
         .alignment(Alignment::Left)
         .wrap(Wrap { trim: true });
 
     f.render_widget(help_paragraph, chunk);
 }
 
+fn render_conflict<B: Backend>(
+    f: &mut Frame<B>,
+    current_path: &Option<String>,
+    theme: &Theme,
+    chunk: Rect,
+) {
+    let text = format!(
+        "{} was changed by another program, and this list has unsaved changes.\n
+        r -- reload the file, dropping your changes
+        k -- keep your changes, the next :w overwrites the file
+        m -- merge both, task by task. where both changed a task, yours wins\n
+        Whichever you pick, u undoes it.",
+        current_path.as_deref().unwrap_or("the file")
+    );
+
+    let paragraph = Paragraph::new(text)
+        .style(theme.fg(theme.text))
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(paragraph, chunk);
+}
+
 fn render_recents<B: Backend>(
     f: &mut Frame<B>,
     recent_files: &mut RecentFiles,
     current_filepath: &Option<String>,
+    theme: &Theme,
     chunk: Rect,
 ) {
     // render the current file as current
@@ -173,14 +301,11 @@ fn render_recents<B: Backend>(
         .map(|s| {
             if let Some(i) = current_filepath {
                 if s == i {
-                    return ListItem::new(Span::styled(
-                        s,
-                        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-                    ));
+                    return ListItem::new(Span::styled(s, theme.current_style()));
                 }
             }
 
-            return ListItem::new(Span::styled(s, Style::default().fg(Color::Gray)));
+            ListItem::new(Span::styled(s, theme.fg(theme.secondary)))
         })
         .collect();
 
@@ -192,28 +317,132 @@ fn render_recents<B: Backend>(
         )
         .style(Style::default())
         .start_corner(tui::layout::Corner::TopRight)
-        .highlight_style(
-            Style::default()
-                .add_modifier(Modifier::REVERSED)
-                .fg(Color::Cyan),
-        );
+        .highlight_style(theme.highlight_style());
 
     f.render_stateful_widget(live_draw_list, chunk, &mut recent_files.state);
 }
 
+fn render_lists<B: Backend>(
+    f: &mut Frame<B>,
+    lists: &mut Lists,
+    current_path: &Option<String>,
+    theme: &Theme,
+    chunk: Rect,
+) {
+    // render the open list as current, like the recent files
+    let items: Vec<ListItem> = lists
+        .items
+        .iter()
+        .map(|s| {
+            let style = match current_path.as_ref() == Some(s) {
+                true => theme.current_style(),
+                false => theme.fg(theme.secondary),
+            };
+            ListItem::new(Span::styled(s, style))
+        })
+        .collect();
+
+    let live_draw_list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::NONE)
+                .border_type(BorderType::Rounded),
+        )
+        .style(Style::default())
+        .start_corner(tui::layout::Corner::TopRight)
+        .highlight_style(theme.highlight_style());
+
+    f.render_stateful_widget(live_draw_list, chunk, &mut lists.state);
+}
+
+fn render_notes<B: Backend>(f: &mut Frame<B>, editor: &NotesEditor, theme: &Theme, chunk: Rect) {
+    // keep the cursor row on screen
+    let scroll = editor
+        .row
+        .saturating_sub(chunk.height.saturating_sub(1) as usize);
+
+    let text: Vec<Spans> = editor
+        .lines
+        .iter()
+        .map(|line| Spans::from(Span::styled(line.clone(), theme.fg(theme.text))))
+        .collect();
+
+    let notes = Paragraph::new(text)
+        .style(Style::default())
+        .alignment(Alignment::Left)
+        .scroll((scroll as u16, 0));
+
+    f.render_widget(notes, chunk);
+
+    let cursor_x = editor.cursor_width() as u16;
+    f.set_cursor(
+        chunk.x + cursor_x.min(chunk.width.saturating_sub(1)),
+        chunk.y + (editor.row - scroll) as u16,
+    );
+}
+
+fn render_backups<B: Backend>(f: &mut Frame<B>, backups: &mut Backups, theme: &Theme, chunk: Rect) {
+    let items: Vec<ListItem> = backups
+        .items
+        .iter()
+        .map(|b| {
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{:>10}  ", b.age()), theme.fg(theme.dim)),
+                Span::styled(b.path.clone(), theme.fg(theme.secondary)),
+            ]))
+        })
+        .collect();
+
+    let live_draw_list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::NONE)
+                .border_type(BorderType::Rounded),
+        )
+        .style(Style::default())
+        .start_corner(tui::layout::Corner::TopRight)
+        .highlight_style(theme.highlight_style());
+
+    f.render_stateful_widget(live_draw_list, chunk, &mut backups.state);
+}
+
+fn render_tags<B: Backend>(f: &mut Frame<B>, tags: &mut TagList, theme: &Theme, chunk: Rect) {
+    let items: Vec<ListItem> = tags
+        .items
+        .iter()
+        .map(|(tag, count)| {
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{:>5}  ", count), theme.fg(theme.dim)),
+                Span::styled(tag.clone(), theme.fg(theme.secondary)),
+            ]))
+        })
+        .collect();
+
+    let live_draw_list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::NONE)
+                .border_type(BorderType::Rounded),
+        )
+        .style(Style::default())
+        .start_corner(tui::layout::Corner::TopRight)
+        .highlight_style(theme.highlight_style());
+
+    f.render_stateful_widget(live_draw_list, chunk, &mut tags.state);
+}
+
 fn render_status_bar<B: Backend>(
     f: &mut Frame<B>,
     screen: &Screen,
-    name: Option<String>,
-    list_state: &ListState,
-    list_length: usize,
+    doolist: &DooList,
+    theme: &Theme,
     chunk: Rect,
 ) {
     let status_block = Block::default()
         .title_alignment(Alignment::Left)
         .borders(Borders::BOTTOM)
         .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(theme.fg(theme.border));
 
     let status_chunks = Layout::default()
         .direction(Direction::Horizontal)
@@ -221,26 +450,42 @@ fn render_status_bar<B: Backend>(
         .split(chunk);
 
     let title = Paragraph::new(match screen {
-        Screen::DooList => match name {
-            Some(n) => n,
-            None => "- ':rename <name>' to name list -".to_string(),
-        },
+        Screen::DooList => format!(
+            "{}{}{}",
+            match doolist.name.clone() {
+                Some(n) => n,
+                None => "- ':rename <name>' to name list -".to_string(),
+            },
+            match doolist.modified {
+                true => " [+]",
+                false => "",
+            },
+            match doolist.filter.is_empty() {
+                true => String::new(),
+                false => format!(" (filter: {})", doolist.filter.join(" ")),
+            }
+        ),
         Screen::Help => "HELP (<esc> to exit)".to_string(),
         Screen::Recents => "Recent files (<esc> to exit)".to_string(),
+        Screen::Backups => "Backups, <enter> to restore (<esc> to exit)".to_string(),
+        Screen::Notes => "Notes (<esc> to save and exit)".to_string(),
+        Screen::Tags => "Tags, <enter> to filter by one (<esc> to exit)".to_string(),
+        Screen::Lists => "Lists, <enter> to open (<esc> to exit)".to_string(),
+        Screen::Conflict => "Changed on disk (<esc> to keep yours)".to_string(),
     })
-    .style(Style::default())
+    .style(theme.fg(theme.text))
     .alignment(Alignment::Left)
     .wrap(Wrap { trim: true });
 
     let widget = Paragraph::new(format!(
         "{}/{}",
-        match list_state.selected() {
+        match doolist.state.selected() {
             Some(i) => format!("{}", i + 1), //TODO: error handlign
             None => "--".to_string(),
         },
-        list_length
+        doolist.visible().len()
     ))
-    .style(Style::default())
+    .style(theme.fg(theme.text))
     .alignment(Alignment::Right)
     .wrap(Wrap { trim: true });
 
@@ -249,28 +494,57 @@ fn render_status_bar<B: Backend>(
     f.render_widget(widget, status_chunks[1]);
 }
 
-fn render_input_bar<B: Backend>(f: &mut Frame<B>, mode: &app::Mode, input: String, chunk: Rect) {
-    let mut input_line_elements = vec![];
-
-    if let app::Mode::Command = mode {
-        input_line_elements.push(Span::styled(":", Style::default().fg(Color::White)));
+fn render_input_bar<B: Backend>(
+    f: &mut Frame<B>,
+    app: &app::App,
+    search_info: Option<String>,
+    chunk: Rect,
+) {
+    let (mode, input, theme) = (&app.mode, &app.input, &app.theme);
+    if let Some(completion) = &app.completion {
+        render_completion(f, completion, theme, chunk);
     }
 
-    input_line_elements.push(Span::styled(&input, Style::default().fg(Color::White)));
+    let prompt = match mode {
+        app::Mode::Command => ":",
+        app::Mode::Search => "/",
+        _ => "",
+    };
+    let mut input_line_elements = vec![Span::styled(prompt, theme.fg(theme.text))];
+
+    match (mode, &app.message) {
+        // messages only take over the line while nothing is being typed
+        (app::Mode::Select | app::Mode::Visual, Some(message)) => {
+            input_line_elements.push(Span::styled(
+                message.text.clone(),
+                match message.severity {
+                    Severity::Info => theme.fg(theme.secondary),
+                    Severity::Error => theme.fg(theme.error),
+                },
+            ))
+        }
+        _ => input_line_elements.push(Span::styled(input.text(), theme.fg(theme.text))),
+    }
 
     let input_line = Spans::from(input_line_elements);
 
-    let (input_title, input_title_style) = match mode {
-        app::Mode::Select => (" Select ", Style::default().fg(Color::LightMagenta)),
-        app::Mode::Search => (" Search ", Style::default().fg(Color::Magenta)),
-        app::Mode::Command => (" Command ", Style::default().fg(Color::Red)),
-        app::Mode::Input => (" Set task name ", Style::default().fg(Color::Yellow)),
+    let (input_title, input_title_style) = match (mode, input.normal) {
+        (app::Mode::Select, _) => (" Select ", theme.fg(theme.mode_select)),
+        (app::Mode::Visual, _) => (" Visual ", theme.fg(theme.mode_visual)),
+        (_, true) => (" Normal ", theme.fg(theme.mode_normal)),
+        (app::Mode::Search, _) => (" Search ", theme.fg(theme.mode_search)),
+        (app::Mode::Command, _) => (" Command ", theme.fg(theme.mode_command)),
+        (app::Mode::Input, _) => (" Set task name ", theme.fg(theme.mode_input)),
     };
 
     // impl len for launcher list
-    let spans = Spans::from(vec![
+    let mut spans = vec![
         Span::styled(input_title, input_title_style), /*, Span::styled(result_diagnostics(), result_diagnostics_style)*/
-    ]);
+    ];
+    if let Some(info) = search_info {
+        spans.push(Span::styled(info, theme.fg(theme.dim)));
+    }
+    let spans = Spans::from(spans);
 
     let input_block = Paragraph::new(input_line)
         .block(
@@ -279,11 +553,62 @@ fn render_input_bar<B: Backend>(f: &mut Frame<B>, mode: &app::Mode, input: Strin
                 .title_alignment(Alignment::Left)
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(Color::DarkGray)),
+                .border_style(theme.fg(theme.border)),
         )
         .style(Style::default())
-        .alignment(Alignment::Left)
-        .wrap(Wrap { trim: true });
+        .alignment(Alignment::Left);
+
+    if let app::Mode::Select | app::Mode::Visual = mode {
+        f.render_widget(input_block.wrap(Wrap { trim: true }), chunk);
+        return;
+    }
+
+    // scroll long lines sideways to keep the cursor in view. widths are in columns, so wide
+    // chars such as CJK or emoji take two
+    let width = chunk.width.saturating_sub(2) as usize;
+    let cursor = prompt.width() + input.cursor_width();
+    let scroll = (cursor + 1).saturating_sub(width);
+    f.render_widget(input_block.scroll((0, scroll as u16)), chunk);
+    f.set_cursor(chunk.x + 1 + (cursor - scroll) as u16, chunk.y + 1);
+}
+
+/// the choices for the word being completed, in a popup sitting on top of the input bar
+fn render_completion<B: Backend>(
+    f: &mut Frame<B>,
+    completion: &Completion,
+    theme: &Theme,
+    chunk: Rect,
+) {
+    let widest = completion
+        .candidates
+        .iter()
+        .map(|c| c.chars().count())
+        .max()
+        .unwrap_or(0) as u16;
+    let height = (completion.candidates.len() as u16).min(8) + 2;
+    let area = Rect {
+        x: chunk.x,
+        y: chunk.y.saturating_sub(height),
+        width: (widest + 4).min(chunk.width),
+        height: height.min(chunk.y),
+    };
+
+    let items: Vec<ListItem> = completion
+        .candidates
+        .iter()
+        .map(|c| ListItem::new(c.as_str()))
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(theme.fg(theme.border)),
+        )
+        .highlight_style(theme.highlight_style());
+    let mut state = ListState::default();
+    state.select(completion.selected);
 
-    f.render_widget(input_block, chunk);
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
 }
@@ -0,0 +1,353 @@
+use crate::config::{ColorSetting, ThemeConfig};
+use crate::error::{DooError, Result};
+use tui::style::{Color, Modifier, Style};
+
+/// the colors of everything doo draws
+pub struct Theme {
+    /// task names and typed input
+    pub text: Color,
+    /// markers and hints that should stay out of the way
+    pub dim: Color,
+    /// entries in the pickers and info messages
+    pub secondary: Color,
+    pub border: Color,
+    /// the selected row, drawn reversed
+    pub highlight: Color,
+    pub done: Color,
+    pub overdue: Color,
+    pub due_today: Color,
+    pub search_match: Color,
+    pub search_match_text: Color,
+    pub priority_a: Color,
+    pub priority_b: Color,
+    pub priority_c: Color,
+    /// priorities below 'C'
+    pub priority_other: Color,
+    /// the open list in the pickers
+    pub current: Color,
+    /// behind the tasks picked in visual mode
+    pub visual: Color,
+    pub error: Color,
+    pub mode_select: Color,
+    pub mode_normal: Color,
+    pub mode_search: Color,
+    pub mode_command: Color,
+    pub mode_input: Color,
+    pub mode_visual: Color,
+    /// behind the whole window, 'reset' keeps the terminal's own
+    pub background: Color,
+}
+
+/// the built in themes, by the name used for 'preset'
+pub const PRESETS: &[&str] = &["default", "light", "solarized-dark", "solarized-light"];
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            text: Color::White,
+            dim: Color::DarkGray,
+            secondary: Color::Gray,
+            border: Color::DarkGray,
+            highlight: Color::Cyan,
+            done: Color::White,
+            overdue: Color::Red,
+            due_today: Color::Yellow,
+            search_match: Color::Yellow,
+            search_match_text: Color::Black,
+            priority_a: Color::Red,
+            priority_b: Color::Yellow,
+            priority_c: Color::Green,
+            priority_other: Color::DarkGray,
+            current: Color::Red,
+            visual: Color::DarkGray,
+            error: Color::Red,
+            mode_select: Color::LightMagenta,
+            mode_normal: Color::Cyan,
+            mode_search: Color::Magenta,
+            mode_command: Color::Red,
+            mode_input: Color::Yellow,
+            mode_visual: Color::LightBlue,
+            background: Color::Reset,
+        }
+    }
+}
+
+mod solarized {
+    use tui::style::Color;
+
+    pub const BASE03: Color = Color::Rgb(0x00, 0x2b, 0x36);
+    pub const BASE02: Color = Color::Rgb(0x07, 0x36, 0x42);
+    pub const BASE01: Color = Color::Rgb(0x58, 0x6e, 0x75);
+    pub const BASE00: Color = Color::Rgb(0x65, 0x7b, 0x83);
+    pub const BASE0: Color = Color::Rgb(0x83, 0x94, 0x96);
+    pub const BASE1: Color = Color::Rgb(0x93, 0xa1, 0xa1);
+    pub const BASE2: Color = Color::Rgb(0xee, 0xe8, 0xd5);
+    pub const BASE3: Color = Color::Rgb(0xfd, 0xf6, 0xe3);
+    pub const YELLOW: Color = Color::Rgb(0xb5, 0x89, 0x00);
+    pub const ORANGE: Color = Color::Rgb(0xcb, 0x4b, 0x16);
+    pub const RED: Color = Color::Rgb(0xdc, 0x32, 0x2f);
+    pub const MAGENTA: Color = Color::Rgb(0xd3, 0x36, 0x82);
+    pub const VIOLET: Color = Color::Rgb(0x6c, 0x71, 0xc4);
+    pub const BLUE: Color = Color::Rgb(0x26, 0x8b, 0xd2);
+    pub const CYAN: Color = Color::Rgb(0x2a, 0xa1, 0x98);
+    pub const GREEN: Color = Color::Rgb(0x85, 0x99, 0x00);
+}
+
+impl Theme {
+    /// the theme from the config: a preset with any single colors laid over it. every color
+    /// or element that can not be read is reported in one error
+    pub fn new(config: &ThemeConfig) -> Result<Theme> {
+        let mut theme = Theme::preset(&config.preset).ok_or_else(|| {
+            DooError::Config(format!(
+                "no theme preset named '{}', pick one of {}",
+                config.preset,
+                PRESETS.join(", ")
+            ))
+        })?;
+
+        let mut problems = Vec::new();
+        for (element, setting) in &config.colors {
+            match (theme.element(element), parse_color(setting)) {
+                (Some(slot), Some(color)) => *slot = color,
+                (None, _) => problems.push(format!("no theme element named '{}'", element)),
+                (_, None) => problems.push(format!("not a color for {}: {}", element, setting)),
+            }
+        }
+        match problems.is_empty() {
+            true => Ok(theme),
+            false => Err(DooError::Config(problems.join(", "))),
+        }
+    }
+
+    pub fn preset(name: &str) -> Option<Theme> {
+        use solarized::*;
+        match name {
+            "default" => Some(Theme::default()),
+            // for terminals with a light background, where white text disappears
+            "light" => Some(Theme {
+                text: Color::Black,
+                dim: Color::DarkGray,
+                secondary: Color::Black,
+                border: Color::DarkGray,
+                highlight: Color::Blue,
+                done: Color::DarkGray,
+                due_today: YELLOW,
+                priority_b: YELLOW,
+                priority_other: Color::DarkGray,
+                mode_select: Color::Magenta,
+                mode_normal: Color::Blue,
+                mode_input: YELLOW,
+                visual: Color::Gray,
+                mode_visual: Color::Blue,
+                ..Theme::default()
+            }),
+            "solarized-dark" => Some(Theme {
+                text: BASE0,
+                dim: BASE01,
+                secondary: BASE1,
+                border: BASE01,
+                highlight: BLUE,
+                done: BASE01,
+                overdue: RED,
+                due_today: YELLOW,
+                search_match: YELLOW,
+                search_match_text: BASE03,
+                priority_a: RED,
+                priority_b: YELLOW,
+                priority_c: GREEN,
+                priority_other: BASE01,
+                current: ORANGE,
+                visual: BASE02,
+                error: RED,
+                mode_select: VIOLET,
+                mode_normal: CYAN,
+                mode_search: MAGENTA,
+                mode_command: RED,
+                mode_input: YELLOW,
+                mode_visual: BLUE,
+                background: BASE03,
+            }),
+            "solarized-light" => Some(Theme {
+                text: BASE00,
+                dim: BASE1,
+                secondary: BASE01,
+                border: BASE1,
+                done: BASE1,
+                search_match_text: BASE3,
+                priority_other: BASE1,
+                visual: BASE2,
+                background: BASE3,
+                ..Theme::preset("solarized-dark")?
+            }),
+            _ => None,
+        }
+    }
+
+    /// the color of an element, by the name used in the 'theme' config section
+    fn element(&mut self, name: &str) -> Option<&mut Color> {
+        Some(match name {
+            "text" => &mut self.text,
+            "dim" => &mut self.dim,
+            "secondary" => &mut self.secondary,
+            "border" => &mut self.border,
+            "highlight" => &mut self.highlight,
+            "done" => &mut self.done,
+            "overdue" => &mut self.overdue,
+            "due_today" => &mut self.due_today,
+            "search_match" => &mut self.search_match,
+            "search_match_text" => &mut self.search_match_text,
+            "priority_a" => &mut self.priority_a,
+            "priority_b" => &mut self.priority_b,
+            "priority_c" => &mut self.priority_c,
+            "priority_other" => &mut self.priority_other,
+            "current" => &mut self.current,
+            "visual" => &mut self.visual,
+            "error" => &mut self.error,
+            "mode_select" => &mut self.mode_select,
+            "mode_normal" => &mut self.mode_normal,
+            "mode_search" => &mut self.mode_search,
+            "mode_command" => &mut self.mode_command,
+            "mode_input" => &mut self.mode_input,
+            "mode_visual" => &mut self.mode_visual,
+            "background" => &mut self.background,
+            _ => return None,
+        })
+    }
+
+    pub fn fg(&self, color: Color) -> Style {
+        Style::default().fg(color)
+    }
+
+    /// the selected row of a list
+    pub fn highlight_style(&self) -> Style {
+        Style::default()
+            .add_modifier(Modifier::REVERSED)
+            .fg(self.highlight)
+    }
+
+    /// a task picked in visual mode, other than the one under the cursor
+    pub fn visual_style(&self) -> Style {
+        Style::default().bg(self.visual)
+    }
+
+    /// the open list among others, in the pickers
+    pub fn current_style(&self) -> Style {
+        Style::default()
+            .fg(self.current)
+            .add_modifier(Modifier::BOLD)
+    }
+}
+
+/// a color as written in the config: a name such as 'red' or 'dark-gray', an index into the
+/// 256 color palette, or '#rrggbb' truecolor
+pub fn parse_color(setting: &ColorSetting) -> Option<Color> {
+    let name = match setting {
+        ColorSetting::Index(i) => return Some(Color::Indexed(*i)),
+        ColorSetting::Name(name) => name.to_lowercase().replace(['-', '_', ' '], ""),
+    };
+    if let Ok(i) = name.parse::<u8>() {
+        return Some(Color::Indexed(i));
+    }
+    if let Some(hex) = name.strip_prefix('#') {
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return match hex.len() {
+            6 => Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?)),
+            _ => None,
+        };
+    }
+    Some(match name.as_str() {
+        "reset" | "default" | "none" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(preset: &str, colors: &[(&str, ColorSetting)]) -> ThemeConfig {
+        ThemeConfig {
+            preset: String::from(preset),
+            colors: colors
+                .iter()
+                .map(|(element, color)| (element.to_string(), color.clone()))
+                .collect(),
+        }
+    }
+
+    fn name(color: &str) -> ColorSetting {
+        ColorSetting::Name(String::from(color))
+    }
+
+    #[test]
+    fn config_colors_are_laid_over_a_preset() {
+        let theme = Theme::new(&config(
+            "solarized-light",
+            &[
+                ("done", name("dark-gray")),
+                ("border", ColorSetting::Index(240)),
+                ("highlight", name("#ff8800")),
+            ],
+        ))
+        .unwrap();
+        assert_eq!(theme.done, Color::DarkGray);
+        assert_eq!(theme.border, Color::Indexed(240));
+        assert_eq!(theme.highlight, Color::Rgb(0xff, 0x88, 0x00));
+        assert_eq!(
+            theme.background,
+            Theme::preset("solarized-light").unwrap().background
+        );
+    }
+
+    #[test]
+    fn every_preset_exists() {
+        for preset in PRESETS {
+            assert!(Theme::new(&config(preset, &[])).is_ok(), "{}", preset);
+        }
+    }
+
+    #[test]
+    fn every_bad_color_is_reported_at_once() {
+        let error = Theme::new(&config(
+            "default",
+            &[("sparkle", name("red")), ("done", name("not-a-color"))],
+        ))
+        .err()
+        .unwrap()
+        .to_string();
+        assert!(error.contains("no theme element named 'sparkle'"));
+        assert!(error.contains("not a color for done: not-a-color"));
+
+        let error = Theme::new(&config("neon", &[])).err().unwrap().to_string();
+        assert!(error.contains("no theme preset named 'neon'") && error.contains("solarized-dark"));
+    }
+
+    #[test]
+    fn colors_are_read_loosely() {
+        let parse = |color: &str| parse_color(&name(color));
+        assert_eq!(parse("Light_Blue"), Some(Color::LightBlue));
+        assert_eq!(parse("grey"), Some(Color::Gray));
+        assert_eq!(parse("none"), Some(Color::Reset));
+        assert_eq!(parse("42"), Some(Color::Indexed(42)));
+        assert_eq!(parse("#00FF7f"), Some(Color::Rgb(0, 0xff, 0x7f)));
+        for bad in ["#fff", "#ff88zz", "#ff88001", "256", "purple", ""] {
+            assert_eq!(parse(bad), None, "{}", bad);
+        }
+    }
+}
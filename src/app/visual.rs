@@ -0,0 +1,134 @@
+use super::doolist::{DooList, ItemPath};
+use std::collections::BTreeSet;
+
+/// the tasks picked in visual mode: a range running from where it started to the cursor, and
+/// any tasks toggled one by one with space
+pub struct Selection {
+    /// the row the range started from, none once it has been settled into `picked`
+    anchor: Option<usize>,
+    picked: BTreeSet<ItemPath>,
+}
+
+impl Selection {
+    /// start a range at the cursor
+    pub fn new(doolist: &DooList) -> Selection {
+        Selection {
+            anchor: doolist.state.selected(),
+            picked: BTreeSet::new(),
+        }
+    }
+
+    /// keep the range picked so far and start a new one at the cursor
+    pub fn restart(&mut self, doolist: &DooList) {
+        self.settle(doolist);
+        self.anchor = doolist.state.selected();
+    }
+
+    /// pick the task under the cursor, or drop it when it is already picked
+    pub fn toggle(&mut self, doolist: &DooList) {
+        self.settle(doolist);
+        if let Some(path) = doolist.selected_path() {
+            if !self.picked.remove(&path) {
+                self.picked.insert(path);
+            }
+        }
+    }
+
+    /// pick exactly `paths`, as after they moved
+    pub fn set(&mut self, paths: Vec<ItemPath>) {
+        self.anchor = None;
+        self.picked = paths.into_iter().collect();
+    }
+
+    /// every picked task, in list order
+    pub fn paths(&self, doolist: &DooList) -> Vec<ItemPath> {
+        let mut paths = self.picked.clone();
+        paths.extend(self.range(doolist));
+        paths.into_iter().collect()
+    }
+
+    /// turn the range into single picks, so the cursor can leave it
+    fn settle(&mut self, doolist: &DooList) {
+        let range = self.range(doolist);
+        self.picked.extend(range);
+        self.anchor = None;
+    }
+
+    fn range(&self, doolist: &DooList) -> Vec<ItemPath> {
+        let (anchor, cursor) = match (self.anchor, doolist.state.selected()) {
+            (Some(anchor), Some(cursor)) => (anchor, cursor),
+            _ => return Vec::new(),
+        };
+        doolist
+            .visible()
+            .into_iter()
+            .skip(anchor.min(cursor))
+            .take(anchor.max(cursor) - anchor.min(cursor) + 1)
+            .map(|v| v.path)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::doolist::DooItem;
+
+    /// a, b, c, d, e with the cursor on c
+    fn doolist() -> DooList {
+        let mut doolist = DooList::new();
+        doolist.list = ["a", "b", "c", "d", "e"]
+            .into_iter()
+            .map(|label| DooItem::new(String::from(label), false))
+            .collect();
+        doolist.state.select(Some(2));
+        doolist
+    }
+
+    #[test]
+    fn range_runs_either_way_from_the_anchor() {
+        let mut doolist = doolist();
+        let selection = Selection::new(&doolist);
+        assert_eq!(selection.paths(&doolist), vec![vec![2]]);
+
+        doolist.state.select(Some(0));
+        assert_eq!(selection.paths(&doolist), vec![vec![0], vec![1], vec![2]]);
+        doolist.state.select(Some(3));
+        assert_eq!(selection.paths(&doolist), vec![vec![2], vec![3]]);
+    }
+
+    #[test]
+    fn toggling_inside_a_range_drops_just_that_task() {
+        let mut doolist = doolist();
+        let mut selection = Selection::new(&doolist);
+        doolist.state.select(Some(4));
+        doolist.state.select(Some(3));
+        selection.toggle(&doolist);
+        // the range is settled first, so moving on no longer grows it
+        doolist.state.select(Some(0));
+        assert_eq!(selection.paths(&doolist), vec![vec![2]]);
+
+        selection.toggle(&doolist);
+        assert_eq!(selection.paths(&doolist), vec![vec![0], vec![2]]);
+        selection.toggle(&doolist);
+        assert_eq!(selection.paths(&doolist), vec![vec![2]]);
+    }
+
+    #[test]
+    fn restarting_keeps_what_was_picked() {
+        let mut doolist = doolist();
+        let mut selection = Selection::new(&doolist);
+        doolist.state.select(Some(1));
+        selection.restart(&doolist);
+        doolist.state.select(Some(0));
+        assert_eq!(selection.paths(&doolist), vec![vec![0], vec![1], vec![2]]);
+
+        // the new range may shrink back to where it started, the old one stays
+        doolist.state.select(Some(1));
+        assert_eq!(selection.paths(&doolist), vec![vec![1], vec![2]]);
+
+        selection.set(vec![vec![3]]);
+        doolist.state.select(Some(0));
+        assert_eq!(selection.paths(&doolist), vec![vec![3]]);
+    }
+}
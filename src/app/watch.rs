@@ -0,0 +1,96 @@
+use super::doolist::DooItem;
+use crate::error::{DooError, Result};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::SystemTime;
+
+/// what a file looked like the last time doo read or wrote it
+#[derive(PartialEq)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl Stamp {
+    fn of(path: &Path) -> Option<Stamp> {
+        fs::metadata(path).ok().map(|meta| Stamp {
+            modified: meta.modified().ok(),
+            len: meta.len(),
+        })
+    }
+}
+
+/// keeps an eye on the open list's file so that changes made by other programs, like a sync
+/// client or another doo, are noticed instead of being overwritten on the next save
+pub struct FileWatcher {
+    path: PathBuf,
+    // dropping the watcher stops the events
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    stamp: Option<Stamp>,
+    /// the items as they were last read from or written to the file, the common ancestor
+    /// when merging edits made on both sides
+    pub base: Vec<DooItem>,
+}
+
+impl FileWatcher {
+    /// start watching `path`, which currently holds `base`
+    pub fn new(path: &str, base: Vec<DooItem>) -> Result<FileWatcher> {
+        let path = PathBuf::from(path);
+        let (tx, events) = mpsc::channel();
+        let error = |e: notify::Error| {
+            DooError::Command(format!("can not watch {}: {}", path.display(), e))
+        };
+
+        // saving replaces the file with a new one, so watch the directory it is in
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let mut watcher = notify::recommended_watcher(tx).map_err(error)?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(error)?;
+
+        Ok(FileWatcher {
+            stamp: Stamp::of(&path),
+            path,
+            _watcher: watcher,
+            events,
+            base,
+        })
+    }
+
+    /// note that the file now holds `base`, because doo itself just read or wrote it
+    pub fn synced(&mut self, base: Vec<DooItem>) {
+        self.stamp = Stamp::of(&self.path);
+        self.base = base;
+    }
+
+    /// whether anything touched the file since the last call, and left it different from
+    /// what doo last read or wrote
+    pub fn poll(&mut self) -> bool {
+        // drain every event, even once one has touched the file
+        let mut touched = false;
+        for event in self.events.try_iter().flatten() {
+            touched |= event.paths.iter().any(|p| p.ends_with(self.file_name()));
+        }
+        touched && self.changed_on_disk()
+    }
+
+    /// whether the file differs from what doo last read or wrote, going by its size and
+    /// modification time
+    pub fn changed_on_disk(&self) -> bool {
+        Stamp::of(&self.path) != self.stamp
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path.is_file()
+    }
+
+    fn file_name(&self) -> &Path {
+        self.path
+            .file_name()
+            .map(Path::new)
+            .unwrap_or(self.path.as_path())
+    }
+}
@@ -0,0 +1,417 @@
+use crate::app::doolist::{DooItem, DooList, ItemPath};
+use crate::app::formats::Format;
+use crate::app::storage;
+use crate::config::DooConfig;
+use crate::error::DooError;
+
+/// everything went fine
+pub const EXIT_OK: i32 = 0;
+/// the list could not be read or written
+pub const EXIT_ERROR: i32 = 1;
+/// the command line itself was wrong
+pub const EXIT_USAGE: i32 = 2;
+/// no task, or more than one task, matched the given index or pattern
+pub const EXIT_NO_MATCH: i32 = 3;
+
+const USAGE: &str = "usage:
+    doo [file]                              open the list in the terminal ui
+    doo add <file> <label>                  add a task, creating the file if needed
+    doo ls <file> [--json|--pending|--done] print the tasks in a list
+    doo done <file> <index|pattern>         mark a task as complete
+    doo rm <file> <index|pattern>           remove a task
+    doo edit <file> <index|pattern> <label> give a task a new label
+    doo mv <file> <index|pattern> <to>      move a task to position <to> among its siblings
+    doo convert <from> <to> [--from <format>] [--to <format>]
+                                            convert a list between doo, todo.txt and markdown
+
+indices start at 1, and subtasks are addressed as 2.1. formats are guessed from the file
+extension (.txt is todo.txt, .md markdown, anything else doo) unless given as 'doo',
+'todo.txt' or 'md'";
+
+pub const SUBCOMMANDS: [&str; 9] = [
+    "add", "ls", "done", "rm", "edit", "mv", "convert", "--help", "-h",
+];
+
+enum CliError {
+    Usage(String),
+    NoMatch(String),
+    Doo(DooError),
+}
+
+impl From<DooError> for CliError {
+    fn from(e: DooError) -> Self {
+        CliError::Doo(e)
+    }
+}
+
+/// run a subcommand without the ui. `args` starts at the subcommand name, and the return
+/// value is the process exit code
+pub fn run(args: &[String], config: &DooConfig) -> i32 {
+    let result = match args.first().map(|a| a.as_str()) {
+        Some("add") => add(&args[1..], config),
+        Some("ls") => ls(&args[1..], config),
+        Some("done") => done(&args[1..], config),
+        Some("rm") => rm(&args[1..], config),
+        Some("edit") => edit(&args[1..], config),
+        Some("mv") => mv(&args[1..], config),
+        Some("convert") => convert(&args[1..], config),
+        Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(CliError::Usage(String::from("unknown subcommand"))),
+    };
+
+    match result {
+        Ok(()) => EXIT_OK,
+        Err(CliError::Usage(message)) => {
+            eprintln!("doo: {}\n\n{}", message, USAGE);
+            EXIT_USAGE
+        }
+        Err(CliError::NoMatch(message)) => {
+            eprintln!("doo: {}", message);
+            EXIT_NO_MATCH
+        }
+        Err(CliError::Doo(e)) => {
+            eprintln!("doo: {}", e);
+            match e {
+                DooError::NoSelection(_) => EXIT_NO_MATCH,
+                _ => EXIT_ERROR,
+            }
+        }
+    }
+}
+
+fn file_arg(args: &[String]) -> Result<String, CliError> {
+    args.first()
+        .map(|f| storage::resolve(f))
+        .ok_or_else(|| CliError::Usage(String::from("missing <file>")))
+}
+
+fn load(path: &str, config: &DooConfig) -> Result<DooList, CliError> {
+    storage::configured_format(&config.storage)?;
+    Ok(storage::open(path, config).load(path)?)
+}
+
+fn save(doolist: &DooList, path: &str, config: &DooConfig) -> Result<(), CliError> {
+    storage::configured_format(&config.storage)?;
+    Ok(storage::open(path, config).save(doolist, path)?)
+}
+
+fn add(args: &[String], config: &DooConfig) -> Result<(), CliError> {
+    let path = file_arg(args)?;
+    let label = args[1..].join(" ");
+    if label.is_empty() {
+        return Err(CliError::Usage(String::from("missing <label>")));
+    }
+
+    let mut doolist = match storage::open(&path, config).exists(&path) {
+        true => load(&path, config)?,
+        false => DooList::new(),
+    };
+    doolist.add_from_label(label);
+    save(&doolist, &path, config)
+}
+
+fn ls(args: &[String], config: &DooConfig) -> Result<(), CliError> {
+    let path = file_arg(args)?;
+    let mut json = false;
+    let mut filter: Option<bool> = None;
+    for flag in &args[1..] {
+        match flag.as_str() {
+            "--json" => json = true,
+            "--pending" => filter = Some(false),
+            "--done" => filter = Some(true),
+            other => return Err(CliError::Usage(format!("unknown flag {}", other))),
+        }
+    }
+
+    let doolist = load(&path, config)?;
+
+    if json {
+        let mut filtered = DooList::new();
+        filtered.name = doolist.name.clone();
+        filtered.list = doolist
+            .list
+            .iter()
+            .filter_map(|item| filter_item(item, filter))
+            .collect();
+        let out =
+            serde_json::to_string_pretty(&filtered).map_err(|e| DooError::serialize(&path, e))?;
+        println!("{}", out);
+        return Ok(());
+    }
+
+    for path in doolist.paths() {
+        let item = match doolist.get(&path) {
+            Some(item) => item,
+            None => continue,
+        };
+        if filter.is_some_and(|complete| item.complete != complete) {
+            continue;
+        }
+        println!(
+            "{}{} [{}] {}{}",
+            "  ".repeat(path.len() - 1),
+            format_index(&path),
+            match item.complete {
+                true => "X",
+                false => " ",
+            },
+            item.priority.map_or(String::new(), |p| format!("({}) ", p)),
+            item.label
+        );
+    }
+    Ok(())
+}
+
+/// keep `item` if it matches the completion filter, along with matching subtasks
+fn filter_item(item: &DooItem, filter: Option<bool>) -> Option<DooItem> {
+    let subtasks: Vec<DooItem> = item
+        .subtasks
+        .iter()
+        .filter_map(|s| filter_item(s, filter))
+        .collect();
+
+    match filter {
+        Some(complete) if item.complete != complete && subtasks.is_empty() => None,
+        _ => Some(DooItem {
+            subtasks,
+            ..item.clone()
+        }),
+    }
+}
+
+fn done(args: &[String], config: &DooConfig) -> Result<(), CliError> {
+    let path = file_arg(args)?;
+    let mut doolist = load(&path, config)?;
+    let task = find_task(&doolist, args.get(1))?;
+    doolist.set_complete_at(&task, true)?;
+    save(&doolist, &path, config)
+}
+
+fn rm(args: &[String], config: &DooConfig) -> Result<(), CliError> {
+    let path = file_arg(args)?;
+    let mut doolist = load(&path, config)?;
+    let task = find_task(&doolist, args.get(1))?;
+    doolist
+        .remove_path(&task)
+        .ok_or(DooError::NoSelection("remove"))?;
+    save(&doolist, &path, config)
+}
+
+fn edit(args: &[String], config: &DooConfig) -> Result<(), CliError> {
+    let path = file_arg(args)?;
+    let label = args.get(2..).unwrap_or_default().join(" ");
+    if label.is_empty() {
+        return Err(CliError::Usage(String::from("missing <label>")));
+    }
+
+    let mut doolist = load(&path, config)?;
+    let task = find_task(&doolist, args.get(1))?;
+    doolist.change_label_at(&task, label)?;
+    save(&doolist, &path, config)
+}
+
+fn mv(args: &[String], config: &DooConfig) -> Result<(), CliError> {
+    let path = file_arg(args)?;
+    let mut doolist = load(&path, config)?;
+    let task = find_task(&doolist, args.get(1))?;
+    let to = args
+        .get(2)
+        .and_then(|to| to.parse::<usize>().ok())
+        .filter(|to| *to > 0)
+        .ok_or_else(|| CliError::Usage(String::from("<to> should be a position from 1")))?;
+    doolist.move_to(&task, to - 1)?;
+    save(&doolist, &path, config)
+}
+
+fn convert(args: &[String], config: &DooConfig) -> Result<(), CliError> {
+    let mut files: Vec<String> = Vec::new();
+    let mut from: Option<Format> = None;
+    let mut to: Option<Format> = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            flag @ ("--from" | "--to") => {
+                let format = args.next().and_then(|f| Format::parse(f)).ok_or_else(|| {
+                    CliError::Usage(format!("{} needs doo, todo.txt or md", flag))
+                })?;
+                match flag {
+                    "--from" => from = Some(format),
+                    _ => to = Some(format),
+                }
+            }
+            file => files.push(storage::resolve(file)),
+        }
+    }
+
+    let (input, output) = match files.as_slice() {
+        [input, output] => (input, output),
+        _ => {
+            return Err(CliError::Usage(String::from(
+                "expected <from> and <to> files",
+            )))
+        }
+    };
+    let from = from.unwrap_or_else(|| Format::from_path(input));
+    let to = to.unwrap_or_else(|| Format::from_path(output));
+    let doolist = storage::with_format(from, 0).load(input)?;
+    Ok(storage::with_format(to, config.backups).save(&doolist, output)?)
+}
+
+fn format_index(path: &[usize]) -> String {
+    path.iter()
+        .map(|i| (i + 1).to_string())
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+/// resolve a 1 based index like '2.1', or else a case insensitive label pattern that has to
+/// match exactly one task
+fn find_task(doolist: &DooList, reference: Option<&String>) -> Result<ItemPath, CliError> {
+    let reference =
+        reference.ok_or_else(|| CliError::Usage(String::from("missing <index|pattern>")))?;
+
+    let index: Option<ItemPath> = reference
+        .split('.')
+        .map(|part| part.parse::<usize>().ok().and_then(|i| i.checked_sub(1)))
+        .collect();
+    if let Some(path) = index {
+        return match doolist.get(&path) {
+            Some(_) => Ok(path),
+            None => Err(CliError::NoMatch(format!("no task at {}", reference))),
+        };
+    }
+
+    let pattern = reference.to_lowercase();
+    let matches: Vec<ItemPath> = doolist
+        .paths()
+        .into_iter()
+        .filter(|path| {
+            doolist
+                .get(path)
+                .is_some_and(|item| item.label.to_lowercase().contains(&pattern))
+        })
+        .collect();
+
+    match matches.len() {
+        0 => Err(CliError::NoMatch(format!(
+            "no task matches '{}'",
+            reference
+        ))),
+        1 => Ok(matches[0].clone()),
+        _ => Err(CliError::NoMatch(format!(
+            "'{}' matches {} tasks ({}), use an index instead",
+            reference,
+            matches.len(),
+            matches
+                .iter()
+                .map(|p| format_index(p))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(label: &str, subtasks: Vec<DooItem>) -> DooItem {
+        let mut item = DooItem::new(String::from(label), false);
+        item.subtasks = subtasks;
+        item
+    }
+
+    /// plan > (design, build), ship
+    fn doolist() -> DooList {
+        let mut doolist = DooList::new();
+        doolist.list = vec![
+            item("plan", vec![item("Design", vec![]), item("build", vec![])]),
+            item("ship", vec![]),
+        ];
+        doolist
+    }
+
+    fn find(reference: &str) -> Result<ItemPath, CliError> {
+        find_task(&doolist(), Some(&String::from(reference)))
+    }
+
+    fn run_with(args: &[&str]) -> i32 {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        let config = DooConfig {
+            backups: 0,
+            ..Default::default()
+        };
+        run(&args, &config)
+    }
+
+    #[test]
+    fn tasks_are_found_by_index_or_by_pattern() {
+        assert!(matches!(find("2"), Ok(path) if path == vec![1]));
+        assert!(matches!(find("1.2"), Ok(path) if path == vec![0, 1]));
+        assert!(matches!(find("desi"), Ok(path) if path == vec![0, 0]));
+        assert!(matches!(find("SHIP"), Ok(path) if path == vec![1]));
+        assert_eq!(format_index(&[0, 1]), "1.2");
+    }
+
+    #[test]
+    fn a_task_that_is_not_there_is_no_match() {
+        assert!(matches!(find("3"), Err(CliError::NoMatch(_))));
+        assert!(matches!(find("1.3"), Err(CliError::NoMatch(_))));
+        assert!(matches!(find("deploy"), Err(CliError::NoMatch(_))));
+        // 0 is not an index, so it is looked for in the labels
+        assert!(matches!(find("0"), Err(CliError::NoMatch(message)) if message.contains("'0'")));
+        assert!(matches!(
+            find("i"),
+            Err(CliError::NoMatch(message)) if message.contains("(1.1, 1.2, 2)")
+        ));
+        assert!(matches!(
+            find_task(&doolist(), None),
+            Err(CliError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn exit_codes_tell_failures_apart() {
+        let dir = std::env::temp_dir().join(format!("doo-cli-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let list = dir.join("list.json").to_string_lossy().to_string();
+        let missing = dir.join("missing.json").to_string_lossy().to_string();
+
+        assert_eq!(run_with(&["fly"]), EXIT_USAGE);
+        assert_eq!(run_with(&["add"]), EXIT_USAGE);
+        assert_eq!(run_with(&["add", &list]), EXIT_USAGE);
+        assert_eq!(run_with(&["ls", &missing]), EXIT_ERROR);
+        assert_eq!(run_with(&["convert", &list]), EXIT_USAGE);
+
+        assert_eq!(run_with(&["add", &list, "buy", "milk"]), EXIT_OK);
+        assert_eq!(run_with(&["add", &list, "call", "mom"]), EXIT_OK);
+        assert_eq!(run_with(&["ls", &list, "--bogus"]), EXIT_USAGE);
+        assert_eq!(run_with(&["done", &list, "tea"]), EXIT_NO_MATCH);
+        assert_eq!(run_with(&["done", &list, "milk"]), EXIT_OK);
+        assert_eq!(run_with(&["mv", &list, "2", "0"]), EXIT_USAGE);
+        assert_eq!(run_with(&["mv", &list, "2", "1"]), EXIT_OK);
+        assert_eq!(run_with(&["rm", &list, "3"]), EXIT_NO_MATCH);
+        assert_eq!(run_with(&["edit", &list, "1"]), EXIT_USAGE);
+        assert_eq!(run_with(&["edit", &list, "tea", "chai"]), EXIT_NO_MATCH);
+        assert_eq!(
+            run_with(&["edit", &list, "milk", "buy", "oat", "milk"]),
+            EXIT_OK
+        );
+
+        let doolist = storage::with_format(Format::Doo, 0).load(&list).unwrap();
+        let labels: Vec<&str> = doolist.list.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, vec!["call mom", "buy oat milk"]);
+        assert!(doolist.list[1].complete);
+
+        assert_eq!(run_with(&["rm", &list, "call"]), EXIT_OK);
+        let todotxt = dir.join("todo.txt").to_string_lossy().to_string();
+        assert_eq!(run_with(&["convert", &list, &todotxt]), EXIT_OK);
+        assert!(std::fs::read_to_string(&todotxt).unwrap().starts_with("x "));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,23 +1,317 @@
+use super::utils;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct Layout {
     pub vertical: String,
     pub horizontal: String,
+    /// an edge or corner such as 'top-right', or 'center', in place of vertical and horizontal
+    pub anchor: Option<String>,
+    /// columns such as 80, or a share of the terminal such as '50%'
+    pub width: Option<SizeSetting>,
+    /// rows such as 20, or a share of the terminal such as '50%'
+    pub height: Option<SizeSetting>,
+    pub min_width: Option<u16>,
+    pub max_width: Option<u16>,
+    pub min_height: Option<u16>,
+    pub max_height: Option<u16>,
+}
+
+impl std::default::Default for Layout {
+    fn default() -> Self {
+        Self {
+            vertical: String::from("center"),
+            horizontal: String::from("center"),
+            anchor: None,
+            width: None,
+            height: None,
+            min_width: None,
+            max_width: None,
+            min_height: None,
+            max_height: None,
+        }
+    }
+}
+
+/// a width or height, either a bare number of cells or written out as in '50%'
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum SizeSetting {
+    Cells(u16),
+    Text(String),
+}
+
+impl std::fmt::Display for SizeSetting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SizeSetting::Cells(cells) => write!(f, "{}", cells),
+            SizeSetting::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// treat the query as a regular expression
+    pub regex: bool,
+    pub ignore_case: bool,
+    /// ignore case unless the query contains an uppercase letter
+    pub smart_case: bool,
+}
+
+impl std::default::Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            regex: false,
+            ignore_case: true,
+            smart_case: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct InputConfig {
+    /// esc in a task name or command switches to vim style normal mode, a second esc drops it
+    pub vim: bool,
+}
+
+/// a color in the theme, either written out or as a bare number from the 256 color palette
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum ColorSetting {
+    Index(u8),
+    Name(String),
+}
+
+impl std::fmt::Display for ColorSetting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorSetting::Index(i) => write!(f, "{}", i),
+            ColorSetting::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    /// the built in theme to start from: 'default', 'light', 'solarized-dark' or
+    /// 'solarized-light'
+    pub preset: String,
+    /// colors of single elements, such as 'text' or 'highlight', over those of the preset
+    #[serde(flatten)]
+    pub colors: BTreeMap<String, ColorSetting>,
+}
+
+impl std::default::Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            preset: String::from("default"),
+            colors: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecentFilesConfig {
+    /// where to keep the recent files list, defaults to '$XDG_STATE_HOME/doo/recent_files.json'
+    pub path: Option<String>,
+    /// how many recent files to remember
+    pub capacity: usize,
+}
+
+impl std::default::Default for RecentFilesConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            capacity: 5,
+        }
+    }
+}
+
+impl RecentFilesConfig {
+    pub fn resolved_path(&self) -> String {
+        configured_path(&self.path, utils::state_dir().join("recent_files.json"))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandHistoryConfig {
+    /// where to keep the command history, defaults to
+    /// '$XDG_STATE_HOME/doo/command_history.json'
+    pub path: Option<String>,
+    /// how many command lines to remember
+    pub capacity: usize,
+}
+
+impl std::default::Default for CommandHistoryConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            capacity: 100,
+        }
+    }
+}
+
+impl CommandHistoryConfig {
+    pub fn resolved_path(&self) -> String {
+        configured_path(&self.path, utils::state_dir().join("command_history.json"))
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct RegistersConfig {
+    /// keep the registers between sessions
+    pub persist: bool,
+    /// where to keep them, defaults to '$XDG_STATE_HOME/doo/registers.json'
+    pub path: Option<String>,
+}
+
+impl RegistersConfig {
+    pub fn resolved_path(&self) -> String {
+        configured_path(&self.path, utils::state_dir().join("registers.json"))
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct DooConfig {
+    #[serde(default)]
     pub layout: Layout,
+    #[serde(default)]
+    pub search: SearchConfig,
+    #[serde(default)]
+    pub input: InputConfig,
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    /// key sequences mapped to the actions they run in select mode, on top of the defaults
+    #[serde(default)]
+    pub keys: BTreeMap<String, String>,
+    /// key sequences mapped to the actions they run in visual mode, on top of the defaults
+    #[serde(default)]
+    pub visual_keys: BTreeMap<String, String>,
+    /// how many rotating '.bak' copies to keep of a list when saving over it
+    #[serde(default = "default_backups")]
+    pub backups: usize,
+    #[serde(default)]
+    pub recent_files: RecentFilesConfig,
+    #[serde(default)]
+    pub command_history: CommandHistoryConfig,
+    #[serde(default)]
+    pub registers: RegistersConfig,
+    /// how many edits can be undone
+    #[serde(default = "default_history_depth")]
+    pub history_depth: usize,
+    /// how lists are stored: 'auto' picks by file extension, or name a format ('doo', 'md',
+    /// 'todo.txt') to use it for every list
+    #[serde(default = "default_storage")]
+    pub storage: String,
+    /// the sqlite database holding 'db://<name>' lists, defaults to
+    /// '$XDG_DATA_HOME/doo/lists.db'
+    #[serde(default)]
+    pub database: Option<String>,
+}
+
+fn default_backups() -> usize {
+    3
+}
+
+fn default_history_depth() -> usize {
+    100
+}
+
+fn default_storage() -> String {
+    String::from("auto")
 }
 
 impl std::default::Default for DooConfig {
     fn default() -> Self {
         Self {
-            layout: Layout {
-                vertical: String::from("center"),
-                horizontal: String::from("center"),
-            }
+            layout: Layout::default(),
+            search: SearchConfig::default(),
+            input: InputConfig::default(),
+            theme: ThemeConfig::default(),
+            keys: BTreeMap::new(),
+            visual_keys: BTreeMap::new(),
+            backups: default_backups(),
+            recent_files: RecentFilesConfig::default(),
+            command_history: CommandHistoryConfig::default(),
+            registers: RegistersConfig::default(),
+            history_depth: default_history_depth(),
+            storage: default_storage(),
+            database: None,
         }
     }
 }
+
+impl DooConfig {
+    pub fn database_path(&self) -> String {
+        configured_path(&self.database, utils::data_dir().join("lists.db"))
+    }
+}
+
+/// a path set in the config, with '~' expanded and made absolute, or `default` when unset
+fn configured_path(path: &Option<String>, default: PathBuf) -> String {
+    match path {
+        Some(path) => utils::get_abs_path_from(utils::expand_home(path)),
+        None => default.to_string_lossy().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unset_paths_go_to_the_xdg_directories() {
+        let state = |file: &str| utils::state_dir().join(file).to_string_lossy().to_string();
+        assert_eq!(
+            RecentFilesConfig::default().resolved_path(),
+            state("recent_files.json")
+        );
+        assert_eq!(
+            CommandHistoryConfig::default().resolved_path(),
+            state("command_history.json")
+        );
+        assert_eq!(
+            RegistersConfig::default().resolved_path(),
+            state("registers.json")
+        );
+        assert_eq!(
+            DooConfig::default().database_path(),
+            utils::data_dir().join("lists.db").to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn set_paths_expand_home_and_become_absolute() {
+        let home = std::env::var("HOME").unwrap();
+        let registers = RegistersConfig {
+            persist: true,
+            path: Some(String::from("~/doo/registers.json")),
+        };
+        assert_eq!(
+            registers.resolved_path(),
+            format!("{}/doo/registers.json", home)
+        );
+
+        let database = DooConfig {
+            database: Some(String::from("lists/../work.db")),
+            ..Default::default()
+        };
+        assert_eq!(
+            database.database_path(),
+            std::env::current_dir()
+                .unwrap()
+                .join("work.db")
+                .to_string_lossy()
+        );
+    }
+}
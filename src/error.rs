@@ -0,0 +1,96 @@
+use std::fmt;
+use std::io;
+
+/// every failure doo knows how to report, with enough context to show the user what went
+/// wrong and where
+#[derive(Debug)]
+pub enum DooError {
+    /// reading or writing a file failed
+    Io { path: String, source: io::Error },
+    /// a file was read but its contents could not be deserialized
+    Serde {
+        path: String,
+        source: serde_json::Error,
+    },
+    /// a list or state could not be serialized to be written out
+    Serialize {
+        path: String,
+        source: serde_json::Error,
+    },
+    /// a query against a list database failed
+    Sqlite {
+        path: String,
+        source: rusqlite::Error,
+    },
+    /// an action needed a selected item and there was none
+    NoSelection(&'static str),
+    /// a command would throw away edits that have not been saved
+    UnsavedChanges,
+    /// a command was given bad or missing arguments
+    Command(String),
+    /// the config file has a setting doo can not use
+    Config(String),
+}
+
+pub type Result<T> = std::result::Result<T, DooError>;
+
+impl DooError {
+    pub fn io(path: &str, source: io::Error) -> DooError {
+        DooError::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    pub fn serde(path: &str, source: serde_json::Error) -> DooError {
+        DooError::Serde {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    pub fn serialize(path: &str, source: serde_json::Error) -> DooError {
+        DooError::Serialize {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    pub fn sqlite(path: &str, source: rusqlite::Error) -> DooError {
+        DooError::Sqlite {
+            path: path.to_string(),
+            source,
+        }
+    }
+}
+
+impl fmt::Display for DooError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DooError::Io { path, source } => write!(f, "{}: {}", path, source),
+            DooError::Serde { path, source } => write!(f, "{}: not a doo list ({})", path, source),
+            DooError::Serialize { path, source } => {
+                write!(f, "{}: could not be written ({})", path, source)
+            }
+            DooError::Sqlite { path, source } => write!(f, "{}: {}", path, source),
+            DooError::NoSelection(action) => write!(f, "no selection to {}", action),
+            DooError::UnsavedChanges => {
+                write!(f, "no write since last change (add ! to override)")
+            }
+            DooError::Command(message) => write!(f, "{}", message),
+            DooError::Config(message) => write!(f, "config: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for DooError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DooError::Io { source, .. } => Some(source),
+            DooError::Serde { source, .. } => Some(source),
+            DooError::Serialize { source, .. } => Some(source),
+            DooError::Sqlite { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -1,5 +1,7 @@
 pub mod app;
+pub mod cli;
 pub mod config;
+pub mod error;
 
 pub fn run(filepath: Option<String>, config: config::DooConfig) {
     let app = app::App::new(filepath, config);
@@ -9,7 +11,7 @@ pub fn run(filepath: Option<String>, config: config::DooConfig) {
 pub mod utils {
     use path_clean::PathClean;
     use std::env;
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
 
     pub fn get_abs_path_from(local_path_str: String) -> String {
         let path = Path::new(&local_path_str);
@@ -23,4 +25,96 @@ pub mod utils {
         // TODO: error handling
         absolute_path.into_os_string().into_string().unwrap()
     }
+
+    /// replace a leading '~' with the home directory
+    pub fn expand_home(path: &str) -> String {
+        match (path.strip_prefix('~'), env::var("HOME")) {
+            (Some(rest), Ok(home)) if rest.is_empty() || rest.starts_with('/') => {
+                format!("{}{}", home, rest)
+            }
+            _ => path.to_string(),
+        }
+    }
+
+    /// where doo keeps data such as list databases: '$XDG_DATA_HOME/doo', falling back to
+    /// '~/.local/share/doo'
+    pub fn data_dir() -> PathBuf {
+        xdg_dir(
+            env::var("XDG_DATA_HOME").ok(),
+            env::var("HOME").ok(),
+            ".local/share",
+        )
+    }
+
+    /// where doo keeps state between sessions, following the XDG base directory spec:
+    /// '$XDG_STATE_HOME/doo', falling back to '~/.local/state/doo'
+    pub fn state_dir() -> PathBuf {
+        xdg_dir(
+            env::var("XDG_STATE_HOME").ok(),
+            env::var("HOME").ok(),
+            ".local/state",
+        )
+    }
+
+    /// doo's directory under the XDG base directory `base`, or under `fallback` in `home` when
+    /// it is not set
+    fn xdg_dir(base: Option<String>, home: Option<String>, fallback: &str) -> PathBuf {
+        let base = match (base, home) {
+            // the spec says relative paths are invalid and should be ignored
+            (Some(dir), _) if Path::new(&dir).is_absolute() => PathBuf::from(dir),
+            (_, Some(home)) => Path::new(&home).join(fallback),
+            (_, None) => env::temp_dir(),
+        };
+        base.join("doo")
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn xdg_dirs_fall_back_to_home() {
+            let home = Some(String::from("/home/me"));
+            assert_eq!(
+                xdg_dir(
+                    Some(String::from("/xdg/state")),
+                    home.clone(),
+                    ".local/state"
+                ),
+                PathBuf::from("/xdg/state/doo")
+            );
+            assert_eq!(
+                xdg_dir(None, home.clone(), ".local/state"),
+                PathBuf::from("/home/me/.local/state/doo")
+            );
+            // a relative base is ignored, as the spec asks
+            assert_eq!(
+                xdg_dir(Some(String::from("state")), home, ".local/share"),
+                PathBuf::from("/home/me/.local/share/doo")
+            );
+            assert_eq!(
+                xdg_dir(None, None, ".local/share"),
+                env::temp_dir().join("doo")
+            );
+        }
+
+        #[test]
+        fn home_is_expanded_only_at_the_start() {
+            let home = env::var("HOME").unwrap();
+            assert_eq!(expand_home("~"), home);
+            assert_eq!(expand_home("~/lists"), format!("{}/lists", home));
+            assert_eq!(expand_home("~lists"), "~lists");
+            assert_eq!(expand_home("lists/~"), "lists/~");
+        }
+
+        #[test]
+        fn relative_paths_are_made_absolute_and_clean() {
+            let cwd = env::current_dir().unwrap();
+            assert_eq!(
+                get_abs_path_from(String::from("a/../b.json")),
+                cwd.join("b.json").to_string_lossy()
+            );
+            assert_eq!(get_abs_path_from(String::from("/x/./y")), "/x/y");
+        }
+    }
 }
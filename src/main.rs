@@ -18,6 +18,7 @@
 //  -- have a command line
 
 use std::env;
+use std::process;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -25,6 +26,12 @@ fn main() {
 
     let config: doo::config::DooConfig = confy::load("doo", "config").unwrap();
 
+    if let Some(subcommand) = filepath {
+        if doo::cli::SUBCOMMANDS.contains(&subcommand.as_str()) {
+            process::exit(doo::cli::run(&args[1..], &config));
+        }
+    }
+
     doo::run(
         match filepath {
             Some(path) => {